@@ -1,4 +1,3 @@
-use iracing::states::Flags;
 use iracing::telemetry::Connection;
 use std::convert::TryInto;
 use std::time::Duration;
@@ -25,8 +24,7 @@ pub fn main() {
         let timecode: f64 = telem.get("SessionTime").unwrap().try_into().unwrap();
         let lap: i32 = telem.get("Lap").unwrap().try_into().unwrap();
 
-        let flags =
-            Flags::from_bits(telem.get("SessionState").unwrap().try_into().unwrap()).unwrap();
+        let flags = telem.session_flags().unwrap();
 
         print!(
             "Lap {lap:>3}: {time:>5.3}s Gear {gear} @ {rpm:>5.0} RPM ",
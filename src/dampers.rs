@@ -0,0 +1,654 @@
+//!
+//! Damper and shock velocity histograms.
+//!
+//! Builds bump/rebound histograms of shock velocity, along with shock deflection ranges, for
+//! each corner of the car, grouped per lap or per track section.
+//!
+//! The analysis is fed with `ShockSample`s, which can be decoded from a live telemetry `Sample`,
+//! or built by hand from any other source.
+//!
+//! **Follow-up:** building histograms from telemetry files (`.ibt`) needs an `.ibt` reader, which
+//! the crate doesn't have yet. It is left for a separate change, along with a `ShockSample` source
+//! over its records.
+
+use crate::tires::Corner;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+use crate::telemetry::{Sample, Value};
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+use std::convert::TryInto;
+
+/// Shock velocity channels (m/s), by corner.
+pub const VELOCITY_CHANNELS: [&str; 4] = ["LFshockVel", "RFshockVel", "LRshockVel", "RRshockVel"];
+
+/// Sub-tick shock velocity channels (m/s), by corner.
+pub const VELOCITY_ST_CHANNELS: [&str; 4] = [
+    "LFshockVel_ST",
+    "RFshockVel_ST",
+    "LRshockVel_ST",
+    "RRshockVel_ST",
+];
+
+/// Shock deflection channels (m), by corner.
+pub const DEFLECTION_CHANNELS: [&str; 4] =
+    ["LFshockDefl", "RFshockDefl", "LRshockDefl", "RRshockDefl"];
+
+/// Sub-tick shock deflection channels (m), by corner.
+pub const DEFLECTION_ST_CHANNELS: [&str; 4] = [
+    "LFshockDefl_ST",
+    "RFshockDefl_ST",
+    "LRshockDefl_ST",
+    "RRshockDefl_ST",
+];
+
+///
+/// Direction of damper travel.
+///
+/// Positive shock velocities are compression (bump), negative are extension (rebound).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    Bump,
+    Rebound,
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bump => write!(f, "bump"),
+            Self::Rebound => write!(f, "rebound"),
+        }
+    }
+}
+
+///
+/// Histogram bin edges.
+///
+/// Edges are given as ascending speeds (m/s) separately for bump and rebound.
+/// Each direction has one more bin than it has edges, the last bin collecting every
+/// velocity above the highest edge.
+///
+/// # Examples
+///
+/// ```
+/// use iracing::dampers::Bins;
+///
+/// // Low speed / high speed split at 25mm/s
+/// let bins = Bins::new(vec![0.025], vec![0.025]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bins {
+    pub bump: Vec<f32>,
+    pub rebound: Vec<f32>,
+}
+
+impl Bins {
+    pub fn new(mut bump: Vec<f32>, mut rebound: Vec<f32>) -> Self {
+        bump.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        rebound.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        Bins { bump, rebound }
+    }
+
+    /// `count` evenly spaced edges, `step` m/s apart, in both directions.
+    pub fn uniform(step: f32, count: usize) -> Self {
+        let edges: Vec<f32> = (1..=count).map(|i| step * i as f32).collect();
+        Bins::new(edges.clone(), edges)
+    }
+
+    fn edges(&self, direction: Direction) -> &[f32] {
+        match direction {
+            Direction::Bump => &self.bump,
+            Direction::Rebound => &self.rebound,
+        }
+    }
+
+    /// Find the direction and bin index for a velocity
+    fn bin(&self, velocity: f32) -> (Direction, usize) {
+        let direction = if velocity >= 0.0 {
+            Direction::Bump
+        } else {
+            Direction::Rebound
+        };
+
+        let speed = velocity.abs();
+        let edges = self.edges(direction);
+        let index = edges.iter().position(|&e| speed < e).unwrap_or(edges.len());
+
+        (direction, index)
+    }
+}
+
+impl Default for Bins {
+    /// 25mm/s bins up to 200mm/s
+    fn default() -> Self {
+        Bins::uniform(0.025, 8)
+    }
+}
+
+///
+/// A single histogram bin
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bucket {
+    pub direction: Direction,
+    /// Lower speed (inclusive, m/s)
+    pub lower: f32,
+    /// Upper speed (exclusive, m/s), `None` for the open-ended last bin
+    pub upper: Option<f32>,
+    pub count: u64,
+}
+
+///
+/// Shock velocity histogram
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "HistogramData")]
+pub struct Histogram {
+    bins: Bins,
+    bump: Vec<u64>,
+    rebound: Vec<u64>,
+}
+
+/// A deserialized `Histogram`, before its counts are checked against its bins
+#[derive(Deserialize)]
+struct HistogramData {
+    bins: Bins,
+    bump: Vec<u64>,
+    rebound: Vec<u64>,
+}
+
+impl TryFrom<HistogramData> for Histogram {
+    type Error = String;
+
+    fn try_from(data: HistogramData) -> Result<Self, Self::Error> {
+        if data.bump.len() != data.bins.bump.len() + 1
+            || data.rebound.len() != data.bins.rebound.len() + 1
+        {
+            return Err(format!(
+                "Histogram has {} bump and {} rebound counts, expected {} and {}",
+                data.bump.len(),
+                data.rebound.len(),
+                data.bins.bump.len() + 1,
+                data.bins.rebound.len() + 1
+            ));
+        }
+
+        Ok(Histogram {
+            bins: data.bins,
+            bump: data.bump,
+            rebound: data.rebound,
+        })
+    }
+}
+
+impl Histogram {
+    pub fn new(bins: Bins) -> Self {
+        Histogram {
+            bump: vec![0; bins.bump.len() + 1],
+            rebound: vec![0; bins.rebound.len() + 1],
+            bins,
+        }
+    }
+
+    /// Add a velocity to the histogram
+    pub fn add(&mut self, velocity: f32) {
+        if !velocity.is_finite() {
+            return;
+        }
+
+        match self.bins.bin(velocity) {
+            (Direction::Bump, i) => self.bump[i] += 1,
+            (Direction::Rebound, i) => self.rebound[i] += 1,
+        }
+    }
+
+    /// Total number of velocities in the histogram
+    pub fn total(&self) -> u64 {
+        self.bump.iter().chain(self.rebound.iter()).sum()
+    }
+
+    /// Number of velocities in the given direction
+    pub fn total_in(&self, direction: Direction) -> u64 {
+        self.counts(direction).iter().sum()
+    }
+
+    /// Counts per bin in the given direction, slowest first
+    pub fn counts(&self, direction: Direction) -> &[u64] {
+        match direction {
+            Direction::Bump => &self.bump,
+            Direction::Rebound => &self.rebound,
+        }
+    }
+
+    /// All bins, rebound first from fastest to slowest, then bump from slowest to fastest.
+    pub fn buckets(&self) -> Vec<Bucket> {
+        let side = |direction: Direction| {
+            let edges = self.bins.edges(direction);
+
+            self.counts(direction)
+                .iter()
+                .enumerate()
+                .map(move |(i, &count)| Bucket {
+                    direction,
+                    lower: if i == 0 { 0.0 } else { edges[i - 1] },
+                    upper: edges.get(i).copied(),
+                    count,
+                })
+        };
+
+        let mut buckets: Vec<Bucket> = side(Direction::Rebound).collect();
+        buckets.reverse();
+        buckets.extend(side(Direction::Bump));
+        buckets
+    }
+}
+
+///
+/// Range of observed values
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Range {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Range {
+    /// Extend a range to include `value`
+    pub fn extend(range: Option<Range>, value: f32) -> Option<Range> {
+        if !value.is_finite() {
+            return range;
+        }
+
+        Some(match range {
+            None => Range {
+                min: value,
+                max: value,
+            },
+            Some(r) => Range {
+                min: r.min.min(value),
+                max: r.max.max(value),
+            },
+        })
+    }
+
+    /// Distance between the minimum and maximum
+    pub fn span(&self) -> f32 {
+        self.max - self.min
+    }
+}
+
+///
+/// Histogram and deflection range for a single corner
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CornerData {
+    pub corner: Corner,
+    pub velocity: Histogram,
+    /// Shock deflection range (m)
+    pub deflection: Option<Range>,
+}
+
+///
+/// A section of the track, given as lap distance percentages (0.0 - 1.0).
+///
+/// A section may wrap over the start/finish line, in which case `start` is greater than `end`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Section {
+    pub name: String,
+    pub start: f32,
+    pub end: f32,
+}
+
+impl Section {
+    pub fn new<S: Into<String>>(name: S, start: f32, end: f32) -> Self {
+        Section {
+            name: name.into(),
+            start,
+            end,
+        }
+    }
+
+    /// Check if a lap distance percentage falls within this section.
+    pub fn contains(&self, pct: f32) -> bool {
+        if self.start <= self.end {
+            pct >= self.start && pct < self.end
+        } else {
+            pct >= self.start || pct < self.end
+        }
+    }
+}
+
+///
+/// How samples are grouped into histograms
+#[derive(Debug, Clone, PartialEq)]
+pub enum Grouping {
+    /// One set of histograms per lap
+    Lap,
+    /// One set of histograms per track section, over all laps.
+    /// Samples outside of every section are discarded.
+    Sections(Vec<Section>),
+}
+
+///
+/// Key of a group of histograms
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Group {
+    Lap(i32),
+    Section(String),
+}
+
+impl Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lap(lap) => write!(f, "Lap {}", lap),
+            Self::Section(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+///
+/// Shock data for a single telemetry tick.
+///
+/// Velocities (m/s) and deflections (m) are given per corner, and may contain several
+/// sub-tick values.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShockSample {
+    pub lap: i32,
+    pub lap_dist_pct: f32,
+    pub velocity: [Vec<f32>; 4],
+    pub deflection: [Vec<f32>; 4],
+}
+
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+fn floats(
+    sample: &Sample,
+    sub_tick: &'static str,
+    single: &'static str,
+) -> Result<Vec<f32>, String> {
+    let value = sample.get(sub_tick).or_else(|_| sample.get(single))?;
+
+    match value {
+        Value::FloatVec(v) => Ok(v),
+        Value::FLOAT(f) => Ok(vec![f]),
+        _ => Err(format!("{}: Value is not a float", single)),
+    }
+}
+
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+impl TryFrom<&Sample> for ShockSample {
+    type Error = String;
+
+    /// Decode shock data from a telemetry sample, preferring the `_ST` sub-tick channels
+    /// where they are available.
+    fn try_from(sample: &Sample) -> Result<Self, Self::Error> {
+        let mut shocks = ShockSample {
+            lap: sample.get("Lap")?.try_into()?,
+            lap_dist_pct: sample.get("LapDistPct")?.try_into()?,
+            ..ShockSample::default()
+        };
+
+        for i in 0..4 {
+            shocks.velocity[i] = floats(sample, VELOCITY_ST_CHANNELS[i], VELOCITY_CHANNELS[i])?;
+            shocks.deflection[i] =
+                floats(sample, DEFLECTION_ST_CHANNELS[i], DEFLECTION_CHANNELS[i])?;
+        }
+
+        Ok(shocks)
+    }
+}
+
+///
+/// A single row of the histogram data frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramRow {
+    pub group: Group,
+    pub corner: Corner,
+    pub direction: Direction,
+    pub lower: f32,
+    pub upper: Option<f32>,
+    pub count: u64,
+    pub deflection_min: Option<f32>,
+    pub deflection_max: Option<f32>,
+}
+
+///
+/// Damper analysis
+///
+/// # Examples
+///
+/// ```
+/// use iracing::dampers::{Bins, DamperAnalysis, Grouping, ShockSample};
+///
+/// let mut analysis = DamperAnalysis::new(Bins::default(), Grouping::Lap);
+///
+/// analysis.push(&ShockSample {
+///     lap: 1,
+///     lap_dist_pct: 0.5,
+///     velocity: [vec![0.01, 0.03], vec![-0.02], vec![0.1], vec![-0.3]],
+///     deflection: [vec![0.05], vec![0.04], vec![0.06], vec![0.07]],
+/// });
+///
+/// println!("{}", analysis.to_json().unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct DamperAnalysis {
+    bins: Bins,
+    grouping: Grouping,
+    groups: BTreeMap<Group, Vec<CornerData>>,
+}
+
+impl DamperAnalysis {
+    pub fn new(bins: Bins, grouping: Grouping) -> Self {
+        DamperAnalysis {
+            bins,
+            grouping,
+            groups: BTreeMap::new(),
+        }
+    }
+
+    fn group_for(&self, sample: &ShockSample) -> Option<Group> {
+        match &self.grouping {
+            Grouping::Lap => Some(Group::Lap(sample.lap)),
+            Grouping::Sections(sections) => sections
+                .iter()
+                .find(|s| s.contains(sample.lap_dist_pct))
+                .map(|s| Group::Section(s.name.clone())),
+        }
+    }
+
+    /// Add a sample to the analysis
+    pub fn push(&mut self, sample: &ShockSample) {
+        let group = match self.group_for(sample) {
+            Some(g) => g,
+            None => return,
+        };
+
+        let bins = &self.bins;
+        let corners = self.groups.entry(group).or_insert_with(|| {
+            Corner::ALL
+                .iter()
+                .map(|&corner| CornerData {
+                    corner,
+                    velocity: Histogram::new(bins.clone()),
+                    deflection: None,
+                })
+                .collect()
+        });
+
+        for (i, data) in corners.iter_mut().enumerate() {
+            for &v in sample.velocity[i].iter() {
+                data.velocity.add(v);
+            }
+
+            for &d in sample.deflection[i].iter() {
+                data.deflection = Range::extend(data.deflection, d);
+            }
+        }
+    }
+
+    /// Histograms for every group, per corner.
+    pub fn groups(&self) -> &BTreeMap<Group, Vec<CornerData>> {
+        &self.groups
+    }
+
+    /// Histograms for a single group, per corner.
+    pub fn group(&self, group: &Group) -> Option<&[CornerData]> {
+        self.groups.get(group).map(|g| g.as_slice())
+    }
+
+    /// Flatten the analysis into a data frame, with one row per group, corner and bin.
+    pub fn rows(&self) -> Vec<HistogramRow> {
+        let mut rows = Vec::new();
+
+        for (group, corners) in self.groups.iter() {
+            for data in corners.iter() {
+                for bucket in data.velocity.buckets() {
+                    rows.push(HistogramRow {
+                        group: group.clone(),
+                        corner: data.corner,
+                        direction: bucket.direction,
+                        lower: bucket.lower,
+                        upper: bucket.upper,
+                        count: bucket.count,
+                        deflection_min: data.deflection.map(|r| r.min),
+                        deflection_max: data.deflection.map(|r| r.max),
+                    });
+                }
+            }
+        }
+
+        rows
+    }
+
+    /// Serialize the data frame as a JSON array of rows
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&self.rows())
+    }
+
+    /// Write the data frame as CSV, including a header row.
+    pub fn write_csv<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(
+            w,
+            "group,corner,direction,lower,upper,count,deflection_min,deflection_max"
+        )?;
+
+        let optional = |v: Option<f32>| v.map(|v| v.to_string()).unwrap_or_default();
+
+        for row in self.rows() {
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{}",
+                csv_escape(&row.group.to_string()),
+                row.corner.prefix(),
+                row.direction,
+                row.lower,
+                optional(row.upper),
+                row.count,
+                optional(row.deflection_min),
+                optional(row.deflection_max),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Quote a CSV field if required
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(lap: i32, pct: f32, velocity: f32, deflection: f32) -> ShockSample {
+        ShockSample {
+            lap,
+            lap_dist_pct: pct,
+            velocity: [
+                vec![velocity],
+                vec![velocity],
+                vec![velocity],
+                vec![velocity],
+            ],
+            deflection: [
+                vec![deflection],
+                vec![deflection],
+                vec![deflection],
+                vec![deflection],
+            ],
+        }
+    }
+
+    #[test]
+    fn histogram_bins() {
+        let mut h = Histogram::new(Bins::new(vec![0.05, 0.025], vec![0.025]));
+
+        for v in [0.0, 0.01, 0.03, 0.2, -0.01, -0.03, f32::NAN].iter() {
+            h.add(*v);
+        }
+
+        assert_eq!(h.counts(Direction::Bump), &[2, 1, 1]);
+        assert_eq!(h.counts(Direction::Rebound), &[1, 1]);
+        assert_eq!(h.total(), 6);
+
+        let buckets = h.buckets();
+        assert_eq!(buckets.len(), 5);
+        assert_eq!(buckets[0].direction, Direction::Rebound);
+        assert_eq!(buckets[0].upper, None);
+        assert_eq!(buckets[4].lower, 0.05);
+    }
+
+    #[test]
+    fn histogram_json() {
+        let mut h = Histogram::new(Bins::uniform(0.025, 2));
+        h.add(0.03);
+
+        let json = serde_json::to_string(&h).unwrap();
+        assert_eq!(serde_json::from_str::<Histogram>(&json).unwrap(), h);
+
+        // Counts which don't match the bins
+        let json = r#"{"bins":{"bump":[0.025],"rebound":[]},"bump":[1],"rebound":[0]}"#;
+        assert!(serde_json::from_str::<Histogram>(json).is_err());
+    }
+
+    #[test]
+    fn grouped_by_section() {
+        let sections = vec![
+            Section::new("Start, finish", 0.9, 0.1),
+            Section::new("Back straight", 0.4, 0.6),
+        ];
+        let mut analysis = DamperAnalysis::new(Bins::default(), Grouping::Sections(sections));
+
+        analysis.push(&sample(1, 0.95, 0.01, 0.05));
+        analysis.push(&sample(2, 0.05, -0.01, 0.03));
+        analysis.push(&sample(2, 0.5, 0.3, 0.04));
+        analysis.push(&sample(2, 0.7, 0.3, 0.04));
+
+        assert_eq!(analysis.groups().len(), 2);
+
+        let start = analysis
+            .group(&Group::Section("Start, finish".to_owned()))
+            .unwrap();
+        assert_eq!(start[0].velocity.total(), 2);
+        assert_eq!(
+            start[3].deflection,
+            Some(Range {
+                min: 0.03,
+                max: 0.05
+            })
+        );
+
+        let mut csv = Vec::new();
+        analysis.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.contains("\"Start, finish\",LF,bump,0,0.025,1,0.03,0.05"));
+        assert_eq!(csv.lines().count(), 1 + analysis.rows().len());
+    }
+}
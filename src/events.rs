@@ -0,0 +1,561 @@
+//!
+//! Race event detection.
+//!
+//! The `EventEngine` compares consecutive `Snapshot`s of the telemetry (and updates to the
+//! session info) and reports what changed between them as typed `Event`s, such as flag changes,
+//! pit road entries and exits, position changes and new best laps.
+
+use crate::session::SessionDetails;
+use crate::session_diff::SessionChange;
+use crate::states::{Flags, SessionState, TrackLocation};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+use crate::telemetry::{Sample, Value};
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+use std::convert::{TryFrom, TryInto};
+
+///
+/// State of a single car at the time of a snapshot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CarSnapshot {
+    /// Overall position (0 when the car has no position)
+    pub position: i32,
+    pub on_pit_road: bool,
+    /// Best lap time this session (s), negative or zero when no lap has been set
+    pub best_lap_time: f32,
+    pub track_surface: TrackLocation,
+}
+
+///
+/// The subset of a telemetry sample used for event detection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub session_time: f64,
+    pub replay_frame: i32,
+    pub session_number: i32,
+    pub session_state: SessionState,
+    pub session_flags: Flags,
+    pub player_car_idx: usize,
+    /// Incident count of the player
+    pub incidents: i32,
+    /// Per car state, indexed by `CarIdx`
+    pub cars: Vec<CarSnapshot>,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Snapshot {
+            session_time: 0.0,
+            replay_frame: 0,
+            session_number: 0,
+            session_state: SessionState::Invalid(0),
+            session_flags: Flags::empty(),
+            player_car_idx: 0,
+            incidents: 0,
+            cars: Vec::new(),
+        }
+    }
+}
+
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+impl TryFrom<&Sample> for Snapshot {
+    type Error = String;
+
+    fn try_from(sample: &Sample) -> Result<Self, Self::Error> {
+        let positions = match sample.get("CarIdxPosition")? {
+            Value::IntVec(v) => v,
+            _ => return Err("CarIdxPosition: Value is not an integer array".to_owned()),
+        };
+        let surfaces = sample.car_idx_track_surface()?;
+        let on_pit_road: Vec<bool> = sample.get("CarIdxOnPitRoad")?.into();
+        let best_laps = match sample.get("CarIdxBestLapTime")? {
+            Value::FloatVec(v) => v,
+            _ => return Err("CarIdxBestLapTime: Value is not a float array".to_owned()),
+        };
+
+        let cars = (0..positions.len())
+            .map(|i| CarSnapshot {
+                position: positions[i],
+                on_pit_road: on_pit_road.get(i).copied().unwrap_or_default(),
+                best_lap_time: best_laps.get(i).copied().unwrap_or_default(),
+                track_surface: surfaces.get(i).copied().unwrap_or_default(),
+            })
+            .collect();
+
+        let player: i32 = sample.get("PlayerCarIdx")?.try_into()?;
+
+        Ok(Snapshot {
+            session_time: sample.get("SessionTime")?.try_into()?,
+            replay_frame: sample.get("ReplayFrameNum")?.try_into()?,
+            session_number: sample.get("SessionNum")?.try_into()?,
+            session_state: sample.session_state()?,
+            session_flags: sample.session_flags()?,
+            player_car_idx: player.max(0) as usize,
+            incidents: sample.get("PlayerCarMyIncidentCount")?.try_into()?,
+            cars,
+        })
+    }
+}
+
+///
+/// Kind of race event, and its details.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum EventKind {
+    FlagsChanged {
+        raised: Flags,
+        cleared: Flags,
+    },
+    SessionStateChanged {
+        from: SessionState,
+        to: SessionState,
+    },
+    SessionChanged {
+        from: i32,
+        to: i32,
+    },
+    PitRoadEntered {
+        car_idx: usize,
+    },
+    PitRoadExited {
+        car_idx: usize,
+    },
+    PositionChanged {
+        car_idx: usize,
+        from: i32,
+        to: i32,
+    },
+    PersonalBest {
+        car_idx: usize,
+        lap_time: f32,
+    },
+    SessionBest {
+        car_idx: usize,
+        lap_time: f32,
+    },
+    Incident {
+        car_idx: usize,
+        added: i32,
+        total: i32,
+    },
+    OffTrack {
+        car_idx: usize,
+    },
+    DriverSwap {
+        car_idx: usize,
+        from_user_id: i64,
+        from_user_name: String,
+        to_user_id: i64,
+        to_user_name: String,
+    },
+}
+
+///
+/// A race event, with the time at which it was detected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub session_time: f64,
+    pub replay_frame: i32,
+
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+impl Event {
+    /// Serialize the event as a single line of JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+}
+
+/// Write events as newline-delimited JSON.
+pub fn write_ndjson<W: Write>(mut w: W, events: &[Event]) -> io::Result<()> {
+    for event in events.iter() {
+        serde_json::to_writer(&mut w, event)?;
+        w.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
+
+///
+/// Event detection engine.
+///
+/// # Examples
+///
+/// ```
+/// use iracing::events::{EventEngine, Snapshot};
+///
+/// let mut engine = EventEngine::default();
+///
+/// for snapshot in vec![Snapshot::default(), Snapshot::default()] {
+///     for event in engine.update(&snapshot) {
+///         println!("{}", event.to_json().unwrap());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EventEngine {
+    previous: Option<Snapshot>,
+    session_best: Option<f32>,
+    session: Option<SessionDetails>,
+}
+
+impl EventEngine {
+    /// Process a new telemetry snapshot, returning events since the previous snapshot.
+    ///
+    /// The first snapshot only establishes the initial state and produces no events.
+    pub fn update(&mut self, current: &Snapshot) -> Vec<Event> {
+        let previous = match self.previous.replace(current.clone()) {
+            Some(p) => p,
+            None => {
+                self.session_best = current
+                    .cars
+                    .iter()
+                    .map(|c| c.best_lap_time)
+                    .filter(|&t| t > 0.0)
+                    .fold(None, |best, t| Some(best.map_or(t, |b: f32| b.min(t))));
+                return Vec::new();
+            }
+        };
+
+        let mut kinds = Vec::new();
+
+        if previous.session_number != current.session_number {
+            self.session_best = None;
+            kinds.push(EventKind::SessionChanged {
+                from: previous.session_number,
+                to: current.session_number,
+            });
+        }
+
+        if previous.session_state != current.session_state {
+            kinds.push(EventKind::SessionStateChanged {
+                from: previous.session_state,
+                to: current.session_state,
+            });
+        }
+
+        if previous.session_flags != current.session_flags {
+            kinds.push(EventKind::FlagsChanged {
+                raised: current.session_flags - previous.session_flags,
+                cleared: previous.session_flags - current.session_flags,
+            });
+        }
+
+        if current.incidents > previous.incidents {
+            kinds.push(EventKind::Incident {
+                car_idx: current.player_car_idx,
+                added: current.incidents - previous.incidents,
+                total: current.incidents,
+            });
+        }
+
+        let new_session = previous.session_number != current.session_number;
+        let mut fastest: Option<(usize, f32)> = None;
+
+        for (car_idx, car) in current.cars.iter().enumerate() {
+            let before = match previous.cars.get(car_idx) {
+                Some(b) => b,
+                None => continue,
+            };
+
+            if !before.on_pit_road && car.on_pit_road {
+                kinds.push(EventKind::PitRoadEntered { car_idx });
+            } else if before.on_pit_road && !car.on_pit_road {
+                kinds.push(EventKind::PitRoadExited { car_idx });
+            }
+
+            if before.position > 0 && car.position > 0 && before.position != car.position {
+                kinds.push(EventKind::PositionChanged {
+                    car_idx,
+                    from: before.position,
+                    to: car.position,
+                });
+            }
+
+            if before.track_surface != TrackLocation::OffTrack
+                && car.track_surface == TrackLocation::OffTrack
+            {
+                kinds.push(EventKind::OffTrack { car_idx });
+            }
+
+            let improved = car.best_lap_time > 0.0
+                && (new_session
+                    || before.best_lap_time <= 0.0
+                    || car.best_lap_time < before.best_lap_time);
+
+            if improved {
+                kinds.push(EventKind::PersonalBest {
+                    car_idx,
+                    lap_time: car.best_lap_time,
+                });
+
+                if !matches!(fastest, Some((_, t)) if car.best_lap_time >= t) {
+                    fastest = Some((car_idx, car.best_lap_time));
+                }
+            }
+        }
+
+        // Only the fastest of the laps set since the previous snapshot can be a session best
+        if let Some((car_idx, lap_time)) = fastest {
+            if !matches!(self.session_best, Some(b) if lap_time >= b) {
+                self.session_best = Some(lap_time);
+                kinds.push(EventKind::SessionBest { car_idx, lap_time });
+            }
+        }
+
+        self.stamp(kinds)
+    }
+
+    /// Process a session info update, returning any driver swaps since the previous update.
+    ///
+    /// Swaps are the `TeamDriverChanged` changes found by `SessionDetails::diff`.
+    /// Events are stamped with the time of the most recent telemetry snapshot.
+    pub fn update_session(&mut self, session: &SessionDetails) -> Vec<Event> {
+        let kinds = match self.session.replace(session.clone()) {
+            Some(previous) => previous
+                .diff(session)
+                .into_iter()
+                .filter_map(|change| match change {
+                    SessionChange::TeamDriverChanged {
+                        car_idx,
+                        from_user_id,
+                        from_user_name,
+                        to_user_id,
+                        to_user_name,
+                        ..
+                    } => Some(EventKind::DriverSwap {
+                        car_idx,
+                        from_user_id,
+                        from_user_name,
+                        to_user_id,
+                        to_user_name,
+                    }),
+                    _ => None,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        self.stamp(kinds)
+    }
+
+    fn stamp(&self, kinds: Vec<EventKind>) -> Vec<Event> {
+        let (session_time, replay_frame) = self
+            .previous
+            .as_ref()
+            .map_or((0.0, 0), |s| (s.session_time, s.replay_frame));
+
+        kinds
+            .into_iter()
+            .map(|kind| Event {
+                session_time,
+                replay_frame,
+                kind,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(time: f64, cars: Vec<CarSnapshot>) -> Snapshot {
+        Snapshot {
+            session_time: time,
+            replay_frame: (time * 60.0) as i32,
+            session_state: SessionState::Racing,
+            session_flags: Flags::GREEN_FLAG,
+            cars,
+            ..Snapshot::default()
+        }
+    }
+
+    fn car(position: i32, on_pit_road: bool, best_lap_time: f32) -> CarSnapshot {
+        CarSnapshot {
+            position,
+            on_pit_road,
+            best_lap_time,
+            track_surface: TrackLocation::OnTrack,
+        }
+    }
+
+    #[test]
+    fn detects_car_events() {
+        let mut engine = EventEngine::default();
+
+        assert!(engine
+            .update(&snapshot(
+                1.0,
+                vec![car(1, false, 90.0), car(2, false, 91.0)]
+            ))
+            .is_empty());
+
+        let mut second = snapshot(2.0, vec![car(2, true, 90.0), car(1, false, 89.5)]);
+        second.session_flags = Flags::YELLOW_FLAG;
+        second.incidents = 2;
+        second.cars[0].track_surface = TrackLocation::OffTrack;
+
+        let events = engine.update(&second);
+        let kinds: Vec<EventKind> = events.iter().map(|e| e.kind.clone()).collect();
+
+        assert!(events
+            .iter()
+            .all(|e| e.session_time == 2.0 && e.replay_frame == 120));
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::FlagsChanged {
+                    raised: Flags::YELLOW_FLAG,
+                    cleared: Flags::GREEN_FLAG,
+                },
+                EventKind::Incident {
+                    car_idx: 0,
+                    added: 2,
+                    total: 2
+                },
+                EventKind::PitRoadEntered { car_idx: 0 },
+                EventKind::PositionChanged {
+                    car_idx: 0,
+                    from: 1,
+                    to: 2
+                },
+                EventKind::OffTrack { car_idx: 0 },
+                EventKind::PositionChanged {
+                    car_idx: 1,
+                    from: 2,
+                    to: 1
+                },
+                EventKind::PersonalBest {
+                    car_idx: 1,
+                    lap_time: 89.5
+                },
+                EventKind::SessionBest {
+                    car_idx: 1,
+                    lap_time: 89.5
+                },
+            ]
+        );
+
+        let mut out = Vec::new();
+        write_ndjson(&mut out, &events).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().count(), events.len());
+        assert!(out.contains(r#""type":"PitRoadEntered","car_idx":0"#));
+    }
+
+    #[test]
+    fn one_session_best_per_tick() {
+        let mut engine = EventEngine::default();
+        engine.update(&snapshot(1.0, vec![car(1, false, 0.0); 3]));
+
+        // Three cars improve on the same tick, only the fastest is a session best
+        let events = engine.update(&snapshot(
+            2.0,
+            vec![
+                car(1, false, 91.0),
+                car(2, false, 90.5),
+                car(3, false, 90.5),
+            ],
+        ));
+        let bests: Vec<_> = events
+            .iter()
+            .filter(|e| matches!(e.kind, EventKind::SessionBest { .. }))
+            .map(|e| e.kind.clone())
+            .collect();
+
+        assert_eq!(
+            bests,
+            vec![EventKind::SessionBest {
+                car_idx: 1,
+                lap_time: 90.5
+            }]
+        );
+
+        let personal = events
+            .iter()
+            .filter(|e| matches!(e.kind, EventKind::PersonalBest { .. }))
+            .count();
+        assert_eq!(personal, 3);
+
+        // Improving personal bests which are slower than the session best
+        let events = engine.update(&snapshot(
+            3.0,
+            vec![
+                car(1, false, 90.9),
+                car(2, false, 90.5),
+                car(3, false, 90.5),
+            ],
+        ));
+        assert_eq!(
+            events.iter().map(|e| e.kind.clone()).collect::<Vec<_>>(),
+            vec![EventKind::PersonalBest {
+                car_idx: 0,
+                lap_time: 90.9
+            }]
+        );
+    }
+
+    #[test]
+    fn session_state_changes() {
+        let mut engine = EventEngine::default();
+        let mut first = snapshot(1.0, vec![]);
+        first.session_state = SessionState::ParadeLaps;
+        engine.update(&first);
+
+        let mut second = snapshot(2.0, vec![]);
+        second.session_number = 1;
+        let kinds: Vec<_> = engine.update(&second).into_iter().map(|e| e.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::SessionChanged { from: 0, to: 1 },
+                EventKind::SessionStateChanged {
+                    from: SessionState::ParadeLaps,
+                    to: SessionState::Racing,
+                },
+            ]
+        );
+        assert!(engine.update(&second).is_empty());
+    }
+
+    #[test]
+    fn driver_swaps() {
+        let before = SessionDetails::from_yaml(include_str!("../fixtures/session.yaml")).unwrap();
+        let mut engine = EventEngine::default();
+        engine.update(&snapshot(10.0, vec![]));
+
+        assert!(engine.update_session(&before).is_empty());
+        assert!(engine.update_session(&before).is_empty());
+
+        // A team mate takes over car 2, and car 3 is taken by a driver of another team
+        let mut after = before.clone();
+        let drivers = &mut after.drivers.other_drivers;
+        drivers[2].user_id = 400001;
+        drivers[2].user_name = "Jo Skitini".to_owned();
+        drivers[3].user_id = 400002;
+        drivers[3].team_id += 1;
+
+        let events = engine.update_session(&after);
+
+        assert_eq!(
+            events,
+            vec![Event {
+                session_time: 10.0,
+                replay_frame: 600,
+                kind: EventKind::DriverSwap {
+                    car_idx: 2,
+                    from_user_id: 315629,
+                    from_user_name: "Freddie Skitini".to_owned(),
+                    to_user_id: 400001,
+                    to_user_name: "Jo Skitini".to_owned(),
+                },
+            }]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//!
+//! Identifying iRacing files from their first few bytes.
+//!
+//! `detect_format` recognises replays (`.rpy`), telemetry files (`.ibt`), session info YAML and
+//! raw snapshots of the telemetry shared memory.
+//!
+//! Setups (`.sto`) are not recognised: their format is undocumented, and without real files to
+//! check against there is no signature to detect them by.
+
+use crate::replay::FILE_MAGIC;
+use std::io::{self, BufRead};
+
+/// Length of the telemetry header shared by `.ibt` files and the shared memory
+const TELEMETRY_HEADER_LENGTH: usize = 112;
+
+/// Maximum number of data buffers in the telemetry header
+const MAX_TELEMETRY_BUFFERS: i32 = 4;
+
+/// Top level keys of the session info, one of which starts the document
+const SESSION_INFO_KEYS: [&str; 8] = [
+    "WeekendInfo:",
+    "SessionInfo:",
+    "QualifyResultsInfo:",
+    "CameraInfo:",
+    "RadioInfo:",
+    "DriverInfo:",
+    "SplitTimeInfo:",
+    "CarSetup:",
+];
+
+/// A file format detected by `detect_format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Replay,
+    /// Telemetry file (`.ibt`), with its telemetry version and tick rate
+    Telemetry {
+        version: i32,
+        tick_rate: i32,
+    },
+    /// Snapshot of the telemetry shared memory, with its telemetry version and tick rate
+    TelemetrySnapshot {
+        version: i32,
+        tick_rate: i32,
+    },
+    SessionInfo,
+}
+
+impl Format {
+    /// The usual file extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Replay => "rpy",
+            Format::Telemetry { .. } => "ibt",
+            Format::TelemetrySnapshot { .. } => "bin",
+            Format::SessionInfo => "yaml",
+        }
+    }
+}
+
+fn word(data: &[u8], n: usize) -> Option<i32> {
+    let bytes = data.get(n * 4..n * 4 + 4)?;
+    Some(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+///
+/// Telemetry header, as `telemetry::Header`.
+///
+/// Telemetry files are written with a single data buffer, while the shared memory rotates through several.
+fn detect_telemetry(data: &[u8]) -> Option<Format> {
+    if data.len() < TELEMETRY_HEADER_LENGTH {
+        return None;
+    }
+
+    let w = |n| word(data, n).unwrap_or_default();
+    let (version, tick_rate) = (w(0), w(2));
+    let (session_info_offset, n_vars, header_offset, n_buffers, buffer_length) =
+        (w(5), w(6), w(7), w(8), w(9));
+
+    let valid = (1..=2).contains(&version)
+        && (1..=1000).contains(&tick_rate)
+        && session_info_offset >= TELEMETRY_HEADER_LENGTH as i32
+        && header_offset >= TELEMETRY_HEADER_LENGTH as i32
+        && n_vars > 0
+        && (1..=MAX_TELEMETRY_BUFFERS).contains(&n_buffers)
+        && buffer_length > 0;
+
+    match (valid, n_buffers) {
+        (false, _) => None,
+        (true, 1) => Some(Format::Telemetry { version, tick_rate }),
+        (true, _) => Some(Format::TelemetrySnapshot { version, tick_rate }),
+    }
+}
+
+/// Session info YAML, with or without the document start marker
+fn detect_session_info(data: &[u8]) -> Option<Format> {
+    let text = String::from_utf8_lossy(&data[..data.len().min(256)]);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    let text = text.strip_prefix("---").unwrap_or(text).trim_start();
+
+    if SESSION_INFO_KEYS.iter().any(|k| text.starts_with(k)) {
+        Some(Format::SessionInfo)
+    } else {
+        None
+    }
+}
+
+///
+/// Identify the format of a file from its first bytes.
+///
+/// A few hundred bytes are enough for every format, fewer may give `None`.
+pub fn detect_format(data: &[u8]) -> Option<Format> {
+    if data.starts_with(FILE_MAGIC) {
+        return Some(Format::Replay);
+    }
+
+    detect_telemetry(data).or_else(|| detect_session_info(data))
+}
+
+///
+/// Identify the format of the data in a `BufRead`, without consuming any of it.
+///
+/// Only the data already buffered (filling it if empty) is examined. If that is shorter than the
+/// telemetry header and no format is found, an `UnexpectedEof` error is returned rather than
+/// `None`, as the data may be a telemetry file cut short by the buffer: use a buffer of at least
+/// a few hundred bytes (a `BufReader` has 8 KiB by default).
+pub fn detect_format_reader<R: BufRead>(reader: &mut R) -> io::Result<Option<Format>> {
+    let data = reader.fill_buf()?;
+
+    match detect_format(data) {
+        None if data.len() < TELEMETRY_HEADER_LENGTH => Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "{} bytes buffered, at least {} are needed to identify the format",
+                data.len(),
+                TELEMETRY_HEADER_LENGTH
+            ),
+        )),
+        format => Ok(format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::fixture;
+    use std::io::{BufReader, Read};
+
+    fn telemetry_header(n_buffers: i32) -> Vec<u8> {
+        let words = [2, 1, 60, 3, 16384, 45200, 270, 144, n_buffers, 1084];
+        let mut data: Vec<u8> = words.iter().flat_map(|w: &i32| w.to_le_bytes()).collect();
+        data.resize(TELEMETRY_HEADER_LENGTH + 32, 0);
+        data
+    }
+
+    #[test]
+    fn formats() {
+        let replay = fixture::replay();
+        assert_eq!(detect_format(&replay), Some(Format::Replay));
+
+        let session = include_bytes!("../fixtures/session.yaml");
+        assert_eq!(detect_format(session), Some(Format::SessionInfo));
+        assert_eq!(
+            detect_format(b"\xef\xbb\xbfWeekendInfo:\n TrackName: iowa\n"),
+            Some(Format::SessionInfo)
+        );
+
+        assert_eq!(
+            detect_format(&telemetry_header(1)),
+            Some(Format::Telemetry {
+                version: 2,
+                tick_rate: 60
+            })
+        );
+        assert_eq!(
+            detect_format(&telemetry_header(3)),
+            Some(Format::TelemetrySnapshot {
+                version: 2,
+                tick_rate: 60
+            })
+        );
+
+        // Too short for the whole telemetry header
+        assert_eq!(detect_format(&telemetry_header(1)[..64]), None);
+
+        let mut setup: Vec<u8> = [3u32, 1, 14].iter().flat_map(|w| w.to_le_bytes()).collect();
+        setup.extend_from_slice(b"mercedesamggt3");
+        assert_eq!(detect_format(&setup), None);
+
+        assert_eq!(detect_format(b"PK\x03\x04 not an iRacing file"), None);
+        assert_eq!(detect_format(b"Hello"), None);
+        assert_eq!(detect_format(b""), None);
+    }
+
+    #[test]
+    fn reader_is_not_consumed() {
+        let data = fixture::replay();
+        let mut reader = BufReader::new(&data[..]);
+
+        assert_eq!(
+            detect_format_reader(&mut reader).unwrap(),
+            Some(Format::Replay)
+        );
+
+        let mut contents = vec![];
+        reader.read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, data);
+    }
+
+    #[test]
+    fn reader_too_short() {
+        let telemetry = telemetry_header(1);
+
+        let mut reader = BufReader::new(&telemetry[..]);
+        assert_eq!(
+            detect_format_reader(&mut reader).unwrap(),
+            Some(Format::Telemetry {
+                version: 2,
+                tick_rate: 60
+            })
+        );
+
+        // A buffer too small to hold the telemetry header
+        let mut reader = BufReader::with_capacity(64, &telemetry[..]);
+        let err = detect_format_reader(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut reader = BufReader::new(&b"Hello"[..]);
+        assert!(detect_format_reader(&mut reader).is_err());
+
+        // Formats which don't need the whole header are still found
+        let mut reader = BufReader::with_capacity(64, &b"WeekendInfo:\n TrackName: iowa\n"[..]);
+        assert_eq!(
+            detect_format_reader(&mut reader).unwrap(),
+            Some(Format::SessionInfo)
+        );
+
+        let mut reader = BufReader::new(&[0u8; TELEMETRY_HEADER_LENGTH][..]);
+        assert_eq!(detect_format_reader(&mut reader).unwrap(), None);
+    }
+}
@@ -1,8 +1,20 @@
 #![deny(clippy::all)]
 
+pub mod dampers;
+pub mod events;
+pub mod format;
+pub mod quantity;
 pub mod replay;
+#[cfg(feature = "async")]
+pub mod replay_async;
+pub mod replay_index;
+pub mod replay_writer;
 pub mod session;
+pub mod session_diff;
+pub mod setups;
+pub mod spotter;
 pub mod states;
+pub mod tires;
 pub mod track_surface;
 
 #[cfg(all(target_os = "windows", feature = "telemetry"))]
@@ -0,0 +1,486 @@
+//!
+//! Unit-aware quantities.
+//!
+//! iRacing describes the unit of every telemetry value with a short string ("m/s", "kPa", "C",
+//! "revs/min", ...), and uses similar strings in the session info ("4.86 km", "60.00 kph").
+//! This module parses these into a `Quantity`, which can be converted between units, and
+//! formatted according to the user's chosen display `Units`.
+
+use crate::states::Units;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+///
+/// The physical dimension of a quantity.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Dimension {
+    Speed,
+    Pressure,
+    Temperature,
+    Angle,
+    Ratio,
+    AngularVelocity,
+    Length,
+    Time,
+    Volume,
+    Mass,
+    Acceleration,
+    /// Plain numbers, and units which are not understood
+    None,
+}
+
+///
+/// Unit of a quantity
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Unit {
+    MetresPerSecond,
+    KilometresPerHour,
+    MilesPerHour,
+
+    Pascal,
+    Kilopascal,
+    Bar,
+    Psi,
+    InchesOfMercury,
+
+    Celsius,
+    Fahrenheit,
+
+    Radians,
+    Degrees,
+
+    Percent,
+    /// A ratio from 0.0 to 1.0, as used by the "%" telemetry values (`Throttle`, `LapDistPct`, ...)
+    Fraction,
+
+    RadiansPerSecond,
+    RevsPerMinute,
+
+    Millimetres,
+    Centimetres,
+    Metres,
+    Kilometres,
+    Feet,
+    Miles,
+
+    Seconds,
+
+    Litres,
+    Gallons,
+
+    Kilograms,
+    Pounds,
+
+    MetresPerSecondSquared,
+
+    /// No unit
+    None,
+
+    /// A unit which is not understood, kept as given
+    Other(String),
+}
+
+impl From<&str> for Unit {
+    /// Parse an iRacing unit string.
+    ///
+    /// Unrecognised units are kept as `Unit::Other`.
+    fn from(s: &str) -> Unit {
+        match s.trim() {
+            "m/s" => Unit::MetresPerSecond,
+            "km/h" | "kph" => Unit::KilometresPerHour,
+            "mph" => Unit::MilesPerHour,
+            "Pa" => Unit::Pascal,
+            "kPa" => Unit::Kilopascal,
+            "bar" => Unit::Bar,
+            "psi" => Unit::Psi,
+            "Hg" | "inHg" => Unit::InchesOfMercury,
+            "C" | "degC" => Unit::Celsius,
+            "F" | "degF" => Unit::Fahrenheit,
+            "rad" => Unit::Radians,
+            "deg" | "degrees" | "°" => Unit::Degrees,
+            "%" => Unit::Percent,
+            "rad/s" => Unit::RadiansPerSecond,
+            "revs/min" | "rpm" | "RPM" => Unit::RevsPerMinute,
+            "mm" => Unit::Millimetres,
+            "cm" => Unit::Centimetres,
+            "m" => Unit::Metres,
+            "km" => Unit::Kilometres,
+            "ft" => Unit::Feet,
+            "mi" => Unit::Miles,
+            "s" | "sec" => Unit::Seconds,
+            "l" | "L" => Unit::Litres,
+            "gal" => Unit::Gallons,
+            "kg" => Unit::Kilograms,
+            "lb" | "lbs" => Unit::Pounds,
+            "m/s^2" => Unit::MetresPerSecondSquared,
+            "" => Unit::None,
+            other => Unit::Other(other.to_owned()),
+        }
+    }
+}
+
+impl Unit {
+    ///
+    /// Parse the unit string of a telemetry value.
+    ///
+    /// As `Unit::from`, except that telemetry "%" values are fractions from 0.0 to 1.0 rather
+    /// than percentages.
+    pub fn telemetry(s: &str) -> Unit {
+        match s.trim() {
+            "%" => Unit::Fraction,
+            other => Unit::from(other),
+        }
+    }
+
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            Self::MetresPerSecond | Self::KilometresPerHour | Self::MilesPerHour => {
+                Dimension::Speed
+            }
+            Self::Pascal | Self::Kilopascal | Self::Bar | Self::Psi | Self::InchesOfMercury => {
+                Dimension::Pressure
+            }
+            Self::Celsius | Self::Fahrenheit => Dimension::Temperature,
+            Self::Radians | Self::Degrees => Dimension::Angle,
+            Self::Percent | Self::Fraction => Dimension::Ratio,
+            Self::RadiansPerSecond | Self::RevsPerMinute => Dimension::AngularVelocity,
+            Self::Millimetres
+            | Self::Centimetres
+            | Self::Metres
+            | Self::Kilometres
+            | Self::Feet
+            | Self::Miles => Dimension::Length,
+            Self::Seconds => Dimension::Time,
+            Self::Litres | Self::Gallons => Dimension::Volume,
+            Self::Kilograms | Self::Pounds => Dimension::Mass,
+            Self::MetresPerSecondSquared => Dimension::Acceleration,
+            Self::None | Self::Other(_) => Dimension::None,
+        }
+    }
+
+    /// Symbol used when displaying a quantity
+    pub fn symbol(&self) -> &str {
+        match self {
+            Self::MetresPerSecond => "m/s",
+            Self::KilometresPerHour => "km/h",
+            Self::MilesPerHour => "mph",
+            Self::Pascal => "Pa",
+            Self::Kilopascal => "kPa",
+            Self::Bar => "bar",
+            Self::Psi => "psi",
+            Self::InchesOfMercury => "inHg",
+            Self::Celsius => "°C",
+            Self::Fahrenheit => "°F",
+            Self::Radians => "rad",
+            Self::Degrees => "°",
+            Self::Percent => "%",
+            Self::Fraction => "",
+            Self::RadiansPerSecond => "rad/s",
+            Self::RevsPerMinute => "rpm",
+            Self::Millimetres => "mm",
+            Self::Centimetres => "cm",
+            Self::Metres => "m",
+            Self::Kilometres => "km",
+            Self::Feet => "ft",
+            Self::Miles => "mi",
+            Self::Seconds => "s",
+            Self::Litres => "l",
+            Self::Gallons => "gal",
+            Self::Kilograms => "kg",
+            Self::Pounds => "lb",
+            Self::MetresPerSecondSquared => "m/s²",
+            Self::None => "",
+            Self::Other(s) => s.as_str(),
+        }
+    }
+
+    /// Scale and offset converting this unit into the base unit of its dimension
+    ///
+    /// Base units are m/s, Pa, °C, rad, fraction (0.0 - 1.0), rad/s, m, s, l, kg and m/s².
+    fn factor(&self) -> (f64, f64) {
+        match self {
+            Self::KilometresPerHour => (1.0 / 3.6, 0.0),
+            Self::MilesPerHour => (0.44704, 0.0),
+            Self::Kilopascal => (1000.0, 0.0),
+            Self::Bar => (100_000.0, 0.0),
+            Self::Psi => (6_894.757, 0.0),
+            Self::InchesOfMercury => (3_386.389, 0.0),
+            Self::Fahrenheit => (5.0 / 9.0, -32.0 * 5.0 / 9.0),
+            Self::Degrees => (std::f64::consts::PI / 180.0, 0.0),
+            Self::Percent => (0.01, 0.0),
+            Self::RevsPerMinute => (std::f64::consts::PI / 30.0, 0.0),
+            Self::Millimetres => (0.001, 0.0),
+            Self::Centimetres => (0.01, 0.0),
+            Self::Kilometres => (1000.0, 0.0),
+            Self::Feet => (0.3048, 0.0),
+            Self::Miles => (1_609.344, 0.0),
+            Self::Gallons => (3.785_411_784, 0.0),
+            Self::Pounds => (0.453_592_37, 0.0),
+            _ => (1.0, 0.0),
+        }
+    }
+
+    /// The unit a quantity of this unit should be displayed in for the given display `Units`.
+    pub fn preferred(&self, units: Units) -> Unit {
+        match (self.dimension(), units) {
+            (Dimension::Speed, Units::Metric) => Unit::KilometresPerHour,
+            (Dimension::Speed, Units::Imperial) => Unit::MilesPerHour,
+            (Dimension::Pressure, Units::Metric) => Unit::Kilopascal,
+            (Dimension::Pressure, Units::Imperial) => Unit::Psi,
+            (Dimension::Temperature, Units::Metric) => Unit::Celsius,
+            (Dimension::Temperature, Units::Imperial) => Unit::Fahrenheit,
+            (Dimension::Angle, _) => Unit::Degrees,
+            (Dimension::Ratio, _) => Unit::Percent,
+            (Dimension::AngularVelocity, _) => Unit::RevsPerMinute,
+            (Dimension::Length, Units::Metric) => match self {
+                Self::Kilometres | Self::Miles => Unit::Kilometres,
+                Self::Millimetres => Unit::Millimetres,
+                _ => Unit::Metres,
+            },
+            (Dimension::Length, Units::Imperial) => match self {
+                Self::Kilometres | Self::Miles => Unit::Miles,
+                _ => Unit::Feet,
+            },
+            (Dimension::Volume, Units::Metric) => Unit::Litres,
+            (Dimension::Volume, Units::Imperial) => Unit::Gallons,
+            (Dimension::Mass, Units::Metric) => Unit::Kilograms,
+            (Dimension::Mass, Units::Imperial) => Unit::Pounds,
+            _ => self.clone(),
+        }
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+///
+/// A value with a unit.
+///
+/// # Examples
+///
+/// ```
+/// use iracing::quantity::{Quantity, Unit};
+/// use iracing::states::Units;
+///
+/// let speed = Quantity::new(50.0, Unit::from("m/s"));
+///
+/// assert_eq!(speed.convert(&Unit::KilometresPerHour).unwrap().value, 180.0);
+/// assert_eq!(speed.format(Units::Imperial, 0), "112 mph");
+///
+/// let length: Quantity = "4.86 km".parse().unwrap();
+/// assert_eq!(length.base(), 4860.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Quantity { value, unit }
+    }
+
+    pub fn dimension(&self) -> Dimension {
+        self.unit.dimension()
+    }
+
+    /// Value in the base unit of the quantity's dimension
+    /// (m/s, Pa, °C, rad, fraction, rad/s, m, s, l, kg or m/s²)
+    pub fn base(&self) -> f64 {
+        let (scale, offset) = self.unit.factor();
+        self.value * scale + offset
+    }
+
+    /// Convert to another unit of the same dimension.
+    ///
+    /// Returns `None` if the units have different dimensions.
+    pub fn convert(&self, to: &Unit) -> Option<Quantity> {
+        if self.unit == *to {
+            return Some(self.clone());
+        }
+
+        if self.dimension() != to.dimension() || self.dimension() == Dimension::None {
+            return None;
+        }
+
+        let (scale, offset) = to.factor();
+
+        Some(Quantity {
+            value: (self.base() - offset) / scale,
+            unit: to.clone(),
+        })
+    }
+
+    /// Convert to the unit used for display with the given `Units`.
+    pub fn preferred(&self, units: Units) -> Quantity {
+        let unit = self.unit.preferred(units);
+        self.convert(&unit).unwrap_or_else(|| self.clone())
+    }
+
+    /// Format for display with the given `Units`, with `precision` decimal places.
+    pub fn format(&self, units: Units, precision: usize) -> String {
+        self.preferred(units).to_string_with(precision)
+    }
+
+    fn to_string_with(&self, precision: usize) -> String {
+        match self.unit {
+            Unit::None | Unit::Fraction => format!("{:.*}", precision, self.value),
+            Unit::Percent | Unit::Degrees | Unit::Celsius | Unit::Fahrenheit => {
+                format!("{:.*}{}", precision, self.value, self.unit)
+            }
+            _ => format!("{:.*} {}", precision, self.value, self.unit),
+        }
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(p) => write!(f, "{}", self.to_string_with(p)),
+            None => match self.unit {
+                Unit::None | Unit::Fraction => write!(f, "{}", self.value),
+                _ => write!(f, "{} {}", self.value, self.unit),
+            },
+        }
+    }
+}
+
+///
+/// Error parsing a quantity from a string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseQuantityError(pub String);
+
+impl Display for ParseQuantityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid quantity \"{}\"", self.0)
+    }
+}
+
+impl Error for ParseQuantityError {}
+
+impl FromStr for Quantity {
+    type Err = ParseQuantityError;
+
+    /// Parse a quantity of the form `<number> <unit>`, such as `4.86 km` or `55.2%`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let split = trimmed
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+            .unwrap_or(trimmed.len());
+
+        let (number, unit) = trimmed.split_at(split);
+        let value = number
+            .parse::<f64>()
+            .map_err(|_| ParseQuantityError(s.to_owned()))?;
+
+        Ok(Quantity::new(value, Unit::from(unit)))
+    }
+}
+
+///
+/// Formats quantities according to a display `Units` preference.
+///
+/// # Examples
+///
+/// ```
+/// use iracing::quantity::{Quantity, QuantityFormatter, Unit};
+/// use iracing::states::Units;
+///
+/// let formatter = QuantityFormatter::new(Units::Metric).precision(1);
+///
+/// assert_eq!(formatter.format(&Quantity::new(171.0, Unit::Kilopascal)), "171.0 kPa");
+/// assert_eq!(formatter.format(&Quantity::new(0.5, Unit::Radians)), "28.6°");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct QuantityFormatter {
+    units: Units,
+    precision: usize,
+}
+
+impl QuantityFormatter {
+    pub fn new(units: Units) -> Self {
+        QuantityFormatter {
+            units,
+            precision: 2,
+        }
+    }
+
+    /// Set the number of decimal places shown
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn format(&self, quantity: &Quantity) -> String {
+        quantity.format(self.units, self.precision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn parse_irsdk_units() {
+        assert_eq!(Unit::from("m/s"), Unit::MetresPerSecond);
+        assert_eq!(Unit::from("kPa").dimension(), Dimension::Pressure);
+        assert_eq!(Unit::from("C").dimension(), Dimension::Temperature);
+        assert_eq!(Unit::from("%").dimension(), Dimension::Ratio);
+        assert_eq!(
+            Unit::from("revs/min").dimension(),
+            Dimension::AngularVelocity
+        );
+        assert_eq!(
+            Unit::from("irsdk_Flags"),
+            Unit::Other("irsdk_Flags".to_owned())
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        let temp = Quantity::new(100.0, Unit::Celsius);
+        assert!(close(temp.convert(&Unit::Fahrenheit).unwrap().value, 212.0));
+
+        let pressure = Quantity::new(180.0, Unit::Kilopascal);
+        assert!(close(
+            pressure.convert(&Unit::Psi).unwrap().value,
+            26.106_794
+        ));
+
+        let rpm = Quantity::new(6000.0, Unit::RevsPerMinute);
+        assert!(close(rpm.base(), 628.318_530_718));
+
+        assert!(temp.convert(&Unit::Psi).is_none());
+    }
+
+    #[test]
+    fn parse_and_format() {
+        let pit_speed: Quantity = "60.00 kph".parse().unwrap();
+        assert!(close(pit_speed.base(), 16.666_666_6));
+        assert_eq!(pit_speed.format(Units::Imperial, 1), "37.3 mph");
+
+        let pct: Quantity = "55.2%".parse().unwrap();
+        assert_eq!(pct.unit, Unit::Percent);
+
+        let throttle = Quantity::new(0.75, Unit::telemetry("%"));
+        assert_eq!(throttle.unit, Unit::Fraction);
+        assert!(close(pct.convert(&Unit::Fraction).unwrap().value, 0.552));
+        assert_eq!(throttle.format(Units::Metric, 1), "75.0%");
+        assert_eq!(Unit::telemetry("kPa"), Unit::Kilopascal);
+
+        let camber: Quantity = "-2.8 deg".parse().unwrap();
+        assert_eq!(format!("{:.1}", camber), "-2.8°");
+
+        assert!("unlimited".parse::<Quantity>().is_err());
+    }
+}
@@ -1,15 +1,43 @@
 use chrono::NaiveDateTime;
+use encoding_rs::mem::decode_latin1;
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
 use std::io;
-use std::io::Read;
 use std::io::Result as IOResult;
 use std::io::{Error as IOError, ErrorKind};
-use std::u32;
+use std::io::{Read, Seek, SeekFrom};
 
 /// Magic number found at the start of replay files
 pub const FILE_MAGIC: &[u8] = b"YLPR";
 
 /// Length of an individual entry in the file preamble.
-const ENTRY_LENGTH: usize = 12;
+pub(crate) const ENTRY_LENGTH: usize = 12;
+
+/// Magic number found at the start of the entrant list
+pub const ENTRANTS_MAGIC: &[u8] = b"SLRD";
+
+/// Length of an entrant record in the entrant list
+pub(crate) const ENTRANT_LENGTH: usize = 652;
+
+/// Length of a session phase record
+pub(crate) const PHASE_LENGTH: usize = 64;
+
+/// Maximum number of zero words between the session phases and the entrant list
+pub(crate) const MAX_PADDING_WORDS: usize = 1024;
+
+/// Maximum number of spaces used to return to word alignment after the asset list
+pub(crate) const MAX_ALIGNMENT_PADDING: usize = 3;
+
+/// Maximum number of entries in the file preamble and entrant list
+pub(crate) const MAX_ENTRIES: usize = 64;
+
+/// Maximum length of the asset list.
+///
+/// The grammar documents up to 1024 bytes, this leaves room for larger fields.
+pub(crate) const MAX_ASSET_LIST_LENGTH: usize = 16 * 1024;
+
+/// Maximum number of session phases
+pub(crate) const MAX_PHASES: usize = 64;
 
 /// A Replay is a pre-recorded stream of data from iRacing which includes metadata
 /// as well as
@@ -20,8 +48,9 @@ pub struct Replay<R: Read> {
 }
 
 /// Header is the top-level header data from a replay.
-/// `Replay::new` will eagerly load this data.
-#[derive(Debug)]
+/// `Replay::new` will eagerly load this data, up to the track name. The session phases and
+/// entrants follow, and are loaded by `Replay::load_entrants` (or `Replay::load`).
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Header {
     pub user_name: String,
     pub timestamp: NaiveDateTime,
@@ -30,38 +59,140 @@ pub struct Header {
     pub session_id: u32,
     pub user_id: u32,
     pub user_car_id: u32,
+    #[serde(default)]
+    pub entry_count: usize, // Number of entries, as given in the file preamble
     pub entries: Vec<Entry>,
+    pub phases: Vec<Phase>,
+    pub assets: Vec<String>, // Assets (track, cars) the sim needs to load the replay
+}
+
+/// A session phase (e.g. practice, qualifying, race) recorded in the replay
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Phase {
+    pub name: String,
+    pub offset: u32, // Offset of the phase's data within the file
+}
+
+/// An RGB colour, from a hex string such as `ff0a00`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Colour {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
 }
 
-#[derive(Debug, Default)]
+impl Colour {
+    /// Parse a 6-digit hex colour, with or without a leading `#` or `0x`
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim();
+        let hex = hex
+            .strip_prefix('#')
+            .or_else(|| hex.strip_prefix("0x"))
+            .unwrap_or(hex);
+
+        if hex.len() != 6 {
+            return None;
+        }
+
+        let value = u32::from_str_radix(hex, 16).ok()?;
+
+        Some(Colour {
+            red: (value >> 16) as u8,
+            green: (value >> 8) as u8,
+            blue: value as u8,
+        })
+    }
+}
+
+/// A paint scheme: a pattern number and up to three colours.
+///
+/// Uses the same `pattern,colour,colour,colour` form as the session info's `CarDesignStr`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Paint {
+    pub pattern: String,
+    pub colours: Vec<Colour>,
+}
+
+impl Paint {
+    fn from_definition(definition: &str) -> Self {
+        let mut parts = definition.split(',');
+
+        Paint {
+            pattern: parts.next().unwrap_or_default().trim().to_owned(),
+            colours: parts.filter_map(Colour::from_hex).collect(),
+        }
+    }
+}
+
+/// Paint schemes of an entry, in the order of the session info's
+/// `CarDesignStr`, `HelmetDesignStr`, `SuitDesignStr` and `CarNumberDesignStr`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Livery {
+    pub colour: Option<Colour>, // Primary colour of the entry
+    pub car: Paint,
+    pub helmet: Paint,
+    pub suit: Paint,
+    pub number: Paint,
+}
+
+/// An entrant in the replay, from the `SLRD` entrant list.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Entry {
-    pub id: i32,
+    pub id: i32, // User ID of the entrant
+    pub car_idx: u32,
+    pub car_number: u32,
     pub car_id: u32,
     pub class_id: u32,
     pub car_name: String,
+    pub driver: Driver,
+    pub livery: Livery,
 }
 
+/// The driver (or team) of an entry
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Driver {
+    pub user_id: i32,
     pub name: String,
 }
 
-impl Default for Header {
-    fn default() -> Self {
-        Header {
-            user_name: String::default(),
-            timestamp: NaiveDateTime::from_timestamp(0, 0),
-            track: String::default(),
-            layout: None,
-            session_id: 0u32,
-            user_id: 0u32,
-            user_car_id: 0u32,
-            entries: Vec::default(),
+impl Entry {
+    /// Decode an entrant record
+    fn from_record(record: &[u8; ENTRANT_LENGTH]) -> Self {
+        let word =
+            |offset: usize| u32::from_le_bytes(record[offset..offset + 4].try_into().unwrap());
+        let user_id = word(16) as i32;
+        let paint = |index: usize| {
+            let start = 180 + index * 32;
+            Paint::from_definition(&latin1_str(&record[start..start + 32]))
+        };
+
+        Entry {
+            id: user_id,
+            car_idx: word(12),
+            car_number: word(24),
+            class_id: word(92),
+            car_id: word(96),
+            car_name: latin1_str(&record[100..164]),
+            driver: Driver {
+                user_id,
+                name: latin1_str(&record[28..92]),
+            },
+            livery: Livery {
+                colour: Colour::from_hex(&latin1_str(&record[164..170])),
+                car: paint(0),
+                helmet: paint(1),
+                suit: paint(2),
+                number: paint(3),
+            },
         }
     }
 }
 
 impl Header {
     /// Load Header data form a `Read`
+    ///
+    /// Reads up to the track name, leaving the reader at the session phases. `phases` and
+    /// `entries` are left empty, see `read_entrants`.
     pub fn from<R: Read>(mut r: R) -> IOResult<Self> {
         let mut result = Self::default();
 
@@ -87,42 +218,60 @@ impl Header {
         // Skip the first entries list (we can get this data and more later in the file)
         // But first we need to know how many entries there are.
         r.read_exact(&mut raw_word)?;
-        let entries_count = u32::from_le_bytes(raw_word) as usize;
+        result.entry_count = bounded(u32::from_le_bytes(raw_word), MAX_ENTRIES, "Entry count")?;
 
         // Read the timestamp (null-terminated string of format YYYY-mm-dd hh:MM:ss) - Up to 32 bytes
         let timestamp_str = read_str(&mut r, 32)?;
 
         // Attempt to parse the timestamp (first 20 chars)
-        match NaiveDateTime::parse_from_str(timestamp_str.as_str(), "%Y-%m-%d %H:%M:%S") {
-            Ok(val) => result.timestamp = val,
-            Err(e) => {
-                println!("Error: {:?} / {}", e, e);
-                return Err(IOError::new(ErrorKind::InvalidInput, e));
-            }
-        }
+        result.timestamp =
+            NaiveDateTime::parse_from_str(timestamp_str.as_str(), "%Y-%m-%d %H:%M:%S")
+                .map_err(|e| IOError::new(ErrorKind::InvalidData, e))?;
 
         // Skip more nothingness
         skip(&mut r, 120)?;
-        skip(&mut r, entries_count * ENTRY_LENGTH)?;
+        skip(&mut r, result.entry_count * ENTRY_LENGTH)?;
 
-        let mut raw_asset_list_length = [0u8; 4];
-        r.read_exact(&mut raw_asset_list_length)?;
+        r.read_exact(&mut raw_word)?;
+        let asset_list_length = bounded(
+            u32::from_le_bytes(raw_word),
+            MAX_ASSET_LIST_LENGTH,
+            "Asset list length",
+        )?;
+        let mut raw_assets = vec![0u8; asset_list_length];
+        r.read_exact(&mut raw_assets)?;
 
-        let asset_list_length = u32::from_le_bytes(raw_asset_list_length) as usize;
-        //read_str(&mut r, asset_list_length);
-        skip(&mut r, asset_list_length)?;
+        result.assets = latin1_str(&raw_assets)
+            .split([',', ';', '\n'])
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(str::to_owned)
+            .collect();
 
         // Skip some more bytes
         skip(&mut r, 6)?;
 
-        // Right now we chomp some spaces until we return to word-alignment
-        // TODO: Chomp the spaces until we return to word alignment.
-        let _padding: Vec<u8> = r
-            .by_ref()
-            .bytes()
-            .take_while(|b| b.as_ref().unwrap() == &b' ')
-            .map(|b| b.unwrap())
-            .collect();
+        // Chomp the spaces until we return to word-alignment, this also consumes
+        // the first byte of the following block.
+        let mut byte = [0u8; 1];
+        let mut padding = 0;
+
+        loop {
+            r.read_exact(&mut byte)?;
+
+            if byte[0] != b' ' {
+                break;
+            }
+
+            padding += 1;
+
+            if padding > MAX_ALIGNMENT_PADDING {
+                return Err(IOError::new(
+                    ErrorKind::InvalidData,
+                    "Too much alignment padding after asset list",
+                ));
+            }
+        }
 
         skip(&mut r, 27)?;
 
@@ -150,27 +299,139 @@ impl Header {
 
         Ok(result)
     }
+
+    ///
+    /// Read the session phases and the `SLRD` entrant list, which follow the fields read by `from`.
+    ///
+    /// Leaves the reader at the start of the replay body. The header is left unchanged if they
+    /// can't be read.
+    pub fn read_entrants<R: Read>(&mut self, mut r: R) -> IOResult<()> {
+        let phases = read_phases(&mut r)?;
+        let entries = read_entries(&mut r, self.entry_count)?;
+
+        self.phases = phases;
+        self.entries = entries;
+
+        Ok(())
+    }
+}
+
+/// Read the session phases, leaving the reader at the first entrant record.
+fn read_phases<R: Read>(mut r: R) -> IOResult<Vec<Phase>> {
+    let mut raw_word = [0u8; 4];
+    let mut phases = vec![];
+
+    skip(&mut r, 128)?;
+
+    // Session phases
+    r.read_exact(&mut raw_word)?;
+    let phases_count = bounded(u32::from_le_bytes(raw_word), MAX_PHASES, "Phase count")?;
+
+    for _ in 0..phases_count {
+        let mut record = [0u8; PHASE_LENGTH];
+        r.read_exact(&mut record)?;
+
+        phases.push(Phase {
+            name: latin1_str(&record[..32]),
+            offset: u32::from_le_bytes(record[32..36].try_into().unwrap()),
+        });
+    }
+
+    // Zero padding runs up to the entrant list
+    let mut padding_words = 0;
+
+    loop {
+        r.read_exact(&mut raw_word)?;
+
+        if raw_word != [0u8; 4] {
+            break;
+        }
+
+        padding_words += 1;
+
+        if padding_words > MAX_PADDING_WORDS {
+            return Err(IOError::new(
+                ErrorKind::InvalidData,
+                "Entrant list not found after session phases",
+            ));
+        }
+    }
+
+    if raw_word != ENTRANTS_MAGIC {
+        return Err(IOError::new(
+            ErrorKind::InvalidData,
+            "Invalid data at start of entrant list",
+        ));
+    }
+
+    skip(&mut r, 8)?;
+
+    Ok(phases)
+}
+
+/// Read `count` records from the entrant list
+fn read_entries<R: Read>(mut r: R, count: usize) -> IOResult<Vec<Entry>> {
+    let mut entries = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let mut record = [0u8; ENTRANT_LENGTH];
+        r.read_exact(&mut record)?;
+        entries.push(Entry::from_record(&record));
+    }
+
+    Ok(entries)
+}
+
+impl Header {
+    /// Find a session phase by name (e.g. `RACE`), ignoring case
+    pub fn phase(&self, name: &str) -> Option<&Phase> {
+        self.phases
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
 }
 
 /// Skip `length` bytes from the reader and discard them.
 #[inline]
 fn skip<R: Read>(mut reader: R, length: usize) -> IOResult<()> {
-    io::copy(&mut reader.by_ref().take(length as u64), &mut io::sink())?;
+    let skipped = io::copy(&mut reader.by_ref().take(length as u64), &mut io::sink())?;
+
+    if skipped < length as u64 {
+        return Err(IOError::new(
+            ErrorKind::UnexpectedEof,
+            "Replay data ended unexpectedly",
+        ));
+    }
+
     Ok(())
 }
 
+/// Check a length or count read from the file is within `max`
+pub(crate) fn bounded(value: u32, max: usize, what: &str) -> IOResult<usize> {
+    let value = value as usize;
+
+    if value > max {
+        return Err(IOError::new(
+            ErrorKind::InvalidData,
+            format!("{} {} exceeds the maximum of {}", what, value, max),
+        ));
+    }
+
+    Ok(value)
+}
+
 // Helper to read `length` bytes from a reader and return it as a `String`
 fn read_str<R: Read>(mut reader: R, length: usize) -> IOResult<String> {
     let mut raw_string_bytes = vec![0u8; length];
     reader.read_exact(&mut raw_string_bytes)?;
 
-    // Find the first null byte
-    let nul = raw_string_bytes
-        .iter()
-        .position(|&b| b == 0)
-        .expect("Given string does not terminate within given length");
+    Ok(latin1_str(&raw_string_bytes))
+}
 
-    Ok(String::from_utf8((&raw_string_bytes[..nul]).to_vec()).unwrap())
+/// Decode a NUL-terminated (or NUL-padded) Latin-1 string
+fn latin1_str(bytes: &[u8]) -> String {
+    let nul = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    decode_latin1(&bytes[..nul]).into_owned()
 }
 
 impl<R: Read> Replay<R> {
@@ -185,6 +446,137 @@ impl<R: Read> Replay<R> {
             metadata,
         })
     }
+
+    /// Create a new replay from a Read, including its session phases and entrants
+    pub fn load(r: R) -> IOResult<Self> {
+        let mut replay = Self::new(r)?;
+        replay.load_entrants()?;
+
+        Ok(replay)
+    }
+
+    ///
+    /// Load the session phases and entrants into `metadata`.
+    ///
+    /// These directly follow the header, so this must be called before anything else is read
+    /// from `reader`.
+    pub fn load_entrants(&mut self) -> IOResult<()> {
+        self.metadata.read_entrants(&mut self.reader)
+    }
+}
+
+/// A replay over a `Read + Seek`, which loads sections of the file as they're needed.
+///
+/// Opening the replay reads the header and indexes the session phases, which give where each
+/// phase's data starts. The entrants are loaded on first use. The layout of the phase data is not
+/// known, so it can be read as raw bytes with `phase_reader`, but not seeked by session time.
+#[derive(Debug)]
+pub struct SeekableReplay<R: Read + Seek> {
+    reader: R,
+    header: Header,
+    entrants_offset: u64,
+    entrants_loaded: bool,
+}
+
+impl<R: Read + Seek> SeekableReplay<R> {
+    /// Open a replay, reading its header and session phases
+    pub fn open(mut r: R) -> IOResult<Self> {
+        r.seek(SeekFrom::Start(0))?;
+        validate_reader(&mut r)?;
+
+        let mut header = Header::from(&mut r)?;
+        header.phases = read_phases(&mut r)?;
+        let entrants_offset = r.stream_position()?;
+
+        Ok(SeekableReplay {
+            reader: r,
+            header,
+            entrants_offset,
+            entrants_loaded: false,
+        })
+    }
+
+    /// Header of the replay. `entries` remains empty until loaded.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Session phases recorded in the replay
+    pub fn phases(&self) -> &[Phase] {
+        &self.header.phases
+    }
+
+    /// Load the entrants, if they haven't been already
+    fn load_entrants(&mut self) -> IOResult<()> {
+        if self.entrants_loaded {
+            return Ok(());
+        }
+
+        self.reader.seek(SeekFrom::Start(self.entrants_offset))?;
+        self.header.entries = read_entries(&mut self.reader, self.header.entry_count)?;
+        self.entrants_loaded = true;
+
+        Ok(())
+    }
+
+    /// Entrants of the replay, loaded from the entrant list on first use
+    pub fn entries(&mut self) -> IOResult<&[Entry]> {
+        self.load_entrants()?;
+        Ok(&self.header.entries)
+    }
+
+    /// Load the entrants and return the complete header
+    pub fn into_header(mut self) -> IOResult<Header> {
+        self.load_entrants()?;
+        Ok(self.header)
+    }
+
+    /// Offset of the replay body, which follows the entrant list
+    pub fn body_offset(&self) -> u64 {
+        self.entrants_offset + (self.header.entry_count * ENTRANT_LENGTH) as u64
+    }
+
+    ///
+    /// Seek to the start of a session phase's data, finding the phase by name (e.g. `RACE`), ignoring case.
+    ///
+    /// Returns the length of the phase's data, which runs up to the start of the next phase, or
+    /// `None` for the last phase, which runs to the end of the file.
+    pub fn seek_to_phase(&mut self, name: &str) -> IOResult<Option<u64>> {
+        let phase = self.header.phase(name).ok_or_else(|| {
+            IOError::new(
+                ErrorKind::NotFound,
+                format!("Session phase {} not found", name),
+            )
+        })?;
+
+        let start = phase.offset as u64;
+
+        if start < self.body_offset() {
+            return Err(IOError::new(
+                ErrorKind::InvalidData,
+                format!("Session phase {} starts before the replay body", name),
+            ));
+        }
+
+        let end = self
+            .header
+            .phases
+            .iter()
+            .map(|p| p.offset as u64)
+            .filter(|&offset| offset > start)
+            .min();
+
+        self.reader.seek(SeekFrom::Start(start))?;
+
+        Ok(end.map(|end| end - start))
+    }
+
+    /// A reader over a session phase's data, found by name as `seek_to_phase`
+    pub fn phase_reader(&mut self, name: &str) -> IOResult<io::Take<&mut R>> {
+        let length = self.seek_to_phase(name)?;
+
+        Ok(self.reader.by_ref().take(length.unwrap_or(u64::MAX)))
+    }
 }
 
 /// Validate the given reader contains contains replay data.
@@ -207,6 +599,183 @@ pub fn validate_reader<R: Read>(mut src: R) -> IOResult<()> {
     }
 }
 
+/// Builds synthetic replay files, laid out as described by `iracing-replay.grammar`.
+#[cfg(test)]
+pub(crate) mod fixture {
+    pub struct Entrant {
+        pub car_idx: u32,
+        pub user_id: i32,
+        pub car_number: u32,
+        pub name: &'static str,
+        pub class_id: u32,
+        pub car_id: u32,
+        pub car_name: &'static str,
+        pub colour: &'static str,
+        pub paints: [&'static str; 4],
+    }
+
+    pub const ENTRANTS: [Entrant; 3] = [
+        Entrant {
+            car_idx: 1,
+            user_id: 81797,
+            car_number: 7,
+            name: "L W Adamek",
+            class_id: 59,
+            car_id: 94,
+            car_name: "Ferrari 488 GT3",
+            colour: "3b3b3b",
+            paints: [
+                "11,3b3b3b,ff1afc,f8f2f2",
+                "0,000000,000000,000000",
+                "0,000000,000000,000000",
+                "0,ffffff,777777,000000",
+            ],
+        },
+        Entrant {
+            car_idx: 2,
+            user_id: 315629,
+            car_number: 23,
+            name: "Freddie Skitini",
+            class_id: 59,
+            car_id: 72,
+            car_name: "Mercedes AMG GT3",
+            colour: "ff0a00",
+            paints: [
+                "1,ff0a00,0834f7,ffffff",
+                "0,000000,000000,000000",
+                "0,000000,000000,000000",
+                "0,ffffff,777777,000000",
+            ],
+        },
+        Entrant {
+            car_idx: 3,
+            user_id: 332748,
+            car_number: 97,
+            name: "Jos\u{e9} M\u{fc}ller",
+            class_id: 59,
+            car_id: 43,
+            car_name: "McLaren MP4-12C GT3",
+            colour: "000000",
+            paints: [
+                "11,000000,25ff2a,555555",
+                "3,ff0000,00ff00,0000ff",
+                "0,000000,000000,000000",
+                "0,ffffff,777777,000000",
+            ],
+        },
+    ];
+
+    fn fixed(out: &mut Vec<u8>, s: &str, length: usize) {
+        let start = out.len();
+        out.extend(s.chars().map(|c| c as u32 as u8));
+        out.resize(start + length, 0);
+    }
+
+    fn word(out: &mut Vec<u8>, v: u32) {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+
+    /// A replay of session 36491425 at iowa oval
+    pub fn replay() -> Vec<u8> {
+        let mut out = b"YLPR".to_vec();
+
+        out.extend_from_slice(&[0u8; 12]);
+        out.extend_from_slice(b"DHPR");
+        out.extend_from_slice(&[0u8; 24]);
+
+        word(&mut out, 81797); // User ID
+        word(&mut out, 94); // User car ID
+        word(&mut out, 0);
+        fixed(&mut out, "L W Adamek", 64);
+        out.extend_from_slice(&[0u8; 8]);
+
+        word(&mut out, ENTRANTS.len() as u32);
+        fixed(&mut out, "2021-03-14 19:30:05", 32);
+        out.extend_from_slice(&[0u8; 120]);
+
+        for e in ENTRANTS.iter() {
+            word(&mut out, e.user_id as u32);
+            word(&mut out, e.car_id);
+            word(&mut out, e.class_id);
+        }
+
+        let assets = "iowa\\oval,ferrari488gt3,mercedesamggt3,mclarenmp4";
+        word(&mut out, assets.len() as u32);
+        fixed(&mut out, assets, assets.len());
+
+        out.push(2);
+        out.extend_from_slice(&[0u8; 5]);
+
+        while out.len() % 4 != 0 {
+            out.push(b' ');
+        }
+
+        out.extend_from_slice(b"FNKW");
+        word(&mut out, 5);
+        word(&mut out, 2380);
+        word(&mut out, 4);
+        out.extend_from_slice(&[0u8; 12]);
+
+        word(&mut out, 36491425); // Session ID
+        out.extend_from_slice(&[0u8; 116]);
+        fixed(&mut out, "iowa\\oval", 64);
+        out.extend_from_slice(&[0u8; 128]);
+
+        let phases = ["PRACTICE", "QUALIFY", "RACE"];
+        word(&mut out, phases.len() as u32);
+
+        let mut phase_offsets = vec![];
+
+        for phase in phases.iter() {
+            fixed(&mut out, phase, 32);
+            phase_offsets.push(out.len());
+            word(&mut out, 0); // Data offset, filled in once the body is written
+            out.extend_from_slice(&[0u8; 28]);
+        }
+
+        out.extend_from_slice(&[0u8; 64]);
+        out.extend_from_slice(b"SLRD");
+        word(&mut out, 1);
+        word(&mut out, 652);
+
+        for e in ENTRANTS.iter() {
+            let start = out.len();
+
+            fixed(&mut out, "RACE", 4);
+            word(&mut out, 2); // Phase number
+            word(&mut out, 0);
+            word(&mut out, e.car_idx);
+            word(&mut out, e.user_id as u32);
+            word(&mut out, 0);
+            word(&mut out, e.car_number);
+            fixed(&mut out, e.name, 64);
+            word(&mut out, e.class_id);
+            word(&mut out, e.car_id);
+            fixed(&mut out, e.car_name, 64);
+            fixed(&mut out, e.colour, 6);
+            out.extend_from_slice(&[0u8; 10]);
+
+            for paint in e.paints.iter() {
+                fixed(&mut out, paint, 32);
+            }
+
+            out.resize(start + 652, 0);
+        }
+
+        // The body's layout isn't known, any bytes will do
+        for (phase, offset) in phase_offsets.into_iter().enumerate() {
+            let start = out.len() as u32;
+            out[offset..offset + 4].copy_from_slice(&start.to_le_bytes());
+            out.extend((0..BODY_PHASE_LENGTH).map(|n| (n * 7 + phase) as u8));
+        }
+
+        out
+    }
+
+    /// Length of each session phase's data in the body
+    pub const BODY_PHASE_LENGTH: usize = 200;
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -242,6 +811,237 @@ mod tests {
         }
     }
 
+    #[test]
+    fn load_entrants() {
+        let data = crate::replay::fixture::replay();
+        let replay = crate::replay::Replay::load(&data[..]).unwrap();
+        let metadata = replay.metadata;
+
+        assert_eq!(metadata.user_id, 81797u32);
+        assert_eq!(metadata.session_id, 36491425u32);
+        assert_eq!(metadata.track, String::from("iowa"));
+        assert_eq!(metadata.layout, Some(String::from("oval")));
+        assert_eq!(metadata.entries.len(), 3);
+
+        let entry = &metadata.entries[2];
+        assert_eq!(entry.id, 332748);
+        assert_eq!(entry.car_idx, 3);
+        assert_eq!(entry.car_number, 97);
+        assert_eq!(entry.class_id, 59);
+        assert_eq!(entry.car_id, 43);
+        assert_eq!(entry.car_name, "McLaren MP4-12C GT3");
+        assert_eq!(entry.driver.user_id, 332748);
+        assert_eq!(entry.driver.name, "Jos\u{e9} M\u{fc}ller");
+    }
+
+    #[test]
+    fn load_phases_and_liveries() {
+        use crate::replay::{Colour, Paint};
+
+        let data = crate::replay::fixture::replay();
+        let metadata = crate::replay::Replay::load(&data[..]).unwrap().metadata;
+
+        let phases: Vec<_> = metadata.phases.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(phases, vec!["PRACTICE", "QUALIFY", "RACE"]);
+        assert!(metadata.phase("race").is_some());
+        assert!(metadata.phase("heat").is_none());
+
+        assert_eq!(
+            metadata.assets,
+            vec![
+                "iowa\\oval",
+                "ferrari488gt3",
+                "mercedesamggt3",
+                "mclarenmp4"
+            ]
+        );
+
+        let livery = &metadata.entries[2].livery;
+        assert_eq!(livery.colour, Some(Colour::default()));
+        assert_eq!(
+            livery.helmet,
+            Paint {
+                pattern: "3".to_owned(),
+                colours: vec![
+                    Colour::from_hex("ff0000").unwrap(),
+                    Colour {
+                        red: 0,
+                        green: 255,
+                        blue: 0
+                    },
+                    Colour::from_hex("#0000ff").unwrap(),
+                ],
+            }
+        );
+        assert_eq!(livery.car.pattern, "11");
+        assert_eq!(livery.number.colours.len(), 3);
+        assert_eq!(Colour::from_hex("0xundefined"), None);
+    }
+
+    #[test]
+    fn reader_left_at_body() {
+        use crate::replay::fixture::BODY_PHASE_LENGTH;
+
+        let data = crate::replay::fixture::replay();
+        let replay = crate::replay::Replay::load(&data[..]).unwrap();
+        let body_offset = replay.metadata.phases[0].offset as usize;
+
+        assert_eq!(replay.reader.len(), 3 * BODY_PHASE_LENGTH);
+        assert_eq!(replay.reader, &data[body_offset..]);
+    }
+
+    #[test]
+    fn seekable_replay() {
+        use crate::replay::{Replay, SeekableReplay};
+        use std::io::Cursor;
+
+        let data = crate::replay::fixture::replay();
+        let mut replay = SeekableReplay::open(Cursor::new(&data)).unwrap();
+
+        assert_eq!(replay.header().session_id, 36491425);
+        assert_eq!(replay.phases().len(), 3);
+        assert!(replay.header().entries.is_empty());
+
+        assert_eq!(replay.entries().unwrap()[0].driver.name, "L W Adamek");
+        assert_eq!(replay.body_offset(), replay.phases()[0].offset as u64);
+
+        let header = replay.into_header().unwrap();
+        assert_eq!(header, Replay::load(&data[..]).unwrap().metadata);
+
+        // Cut short in the entrant list: the header and phases open, the entrants fail to load
+        let mut replay = SeekableReplay::open(Cursor::new(&data[..1000])).unwrap();
+        assert_eq!(replay.header().session_id, 36491425);
+        assert_eq!(replay.phases().len(), 3);
+
+        let err = replay.entries().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert!(replay.header().entries.is_empty());
+    }
+
+    #[test]
+    fn seek_to_phase() {
+        use crate::replay::fixture::BODY_PHASE_LENGTH;
+        use crate::replay::SeekableReplay;
+        use std::io::{Cursor, Read};
+
+        let data = crate::replay::fixture::replay();
+        let mut replay = SeekableReplay::open(Cursor::new(&data)).unwrap();
+        let body_offset = replay.body_offset() as usize;
+
+        for (n, name) in ["practice", "QUALIFY", "Race"].iter().enumerate() {
+            let mut phase = vec![];
+            replay
+                .phase_reader(name)
+                .unwrap()
+                .read_to_end(&mut phase)
+                .unwrap();
+
+            let start = body_offset + n * BODY_PHASE_LENGTH;
+            assert_eq!(phase.len(), BODY_PHASE_LENGTH, "{}", name);
+            assert_eq!(phase, &data[start..start + BODY_PHASE_LENGTH], "{}", name);
+        }
+
+        // Phases can be read in any order
+        assert_eq!(
+            replay.seek_to_phase("QUALIFY").unwrap(),
+            Some(BODY_PHASE_LENGTH as u64)
+        );
+        assert_eq!(replay.seek_to_phase("RACE").unwrap(), None);
+        let mut first = [0u8; 4];
+        replay
+            .phase_reader("PRACTICE")
+            .unwrap()
+            .read_exact(&mut first)
+            .unwrap();
+        assert_eq!(first, data[body_offset..body_offset + 4]);
+
+        let err = replay.seek_to_phase("HEAT").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        // A phase offset pointing back into the header
+        let mut corrupt = data.clone();
+        let offset = data.windows(4).position(|w| w == b"RACE").unwrap() + 32;
+        corrupt[offset..offset + 4].copy_from_slice(&16u32.to_le_bytes());
+
+        let mut replay = SeekableReplay::open(Cursor::new(&corrupt)).unwrap();
+        let err = replay.seek_to_phase("RACE").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn truncated_replays() {
+        let data = crate::replay::fixture::replay();
+        let replay = crate::replay::Replay::load(&data[..]).unwrap();
+        let header_length =
+            data.len() - crate::replay::Replay::new(&data[..]).unwrap().reader.len();
+        let body_offset = replay.metadata.phases[0].offset as usize;
+
+        for length in 0..body_offset {
+            let truncated = &data[..length];
+
+            assert_eq!(
+                crate::replay::Replay::new(truncated).is_err(),
+                length < header_length,
+                "Truncated to {} bytes",
+                length
+            );
+            assert!(
+                crate::replay::Replay::load(truncated).is_err(),
+                "Truncated to {} bytes",
+                length
+            );
+        }
+    }
+
+    #[test]
+    fn corrupt_replays() {
+        let data = crate::replay::fixture::replay();
+
+        // Entry count and asset list length beyond their bounds
+        for (offset, value) in [(128u32, 65u32), (320, 1 << 30)].iter() {
+            let mut corrupt = data.clone();
+            let offset = *offset as usize;
+            corrupt[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+
+            let err = crate::replay::Replay::new(&corrupt[..]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+
+        // Entrant list magic missing: the header still loads, the entrants don't
+        let mut corrupt = data.clone();
+        let magic = data.windows(4).position(|w| w == b"SLRD").unwrap();
+        corrupt[magic] = b'X';
+
+        let mut replay = crate::replay::Replay::new(&corrupt[..]).unwrap();
+        assert_eq!(replay.metadata.session_id, 36491425);
+        assert_eq!(replay.metadata.entry_count, 3);
+        let err = replay.load_entrants().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // User name without a NUL terminator
+        let mut unterminated = data.clone();
+        unterminated[56..120].copy_from_slice(&[0xe9; 64]);
+        let metadata = crate::replay::Replay::new(&unterminated[..])
+            .unwrap()
+            .metadata;
+        assert_eq!(metadata.user_name, "\u{e9}".repeat(64));
+
+        // Flip bytes throughout the header; every outcome is fine as long as it doesn't panic
+        let mut state = 0x2545_f491u32;
+
+        for _ in 0..2000 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+
+            let mut corrupt = data.clone();
+            let offset = state as usize % corrupt.len();
+            corrupt[offset] = (state >> 24) as u8;
+
+            let _ = crate::replay::Replay::load(&corrupt[..]);
+        }
+    }
+
     #[test]
     fn load_metadata() {
         let mut replay_file = File::open("./subses36491425.rpy").unwrap();
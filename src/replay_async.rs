@@ -0,0 +1,236 @@
+//!
+//! Replay parsing over `tokio::io::AsyncRead`, enabled with the `async` feature.
+//!
+//! The header and entrant list are made of fixed-length fields, apart from a few counts and
+//! lengths which give the length of what follows. Each part is read into a buffer using these
+//! lengths, then parsed once with the same code as `replay::Header::from` and
+//! `Header::read_entrants`, so gives identical values. The file magic is checked before anything
+//! else is read, and the reader is never read past the part being parsed.
+
+use crate::replay::{
+    bounded, Header, ENTRANTS_MAGIC, ENTRANT_LENGTH, ENTRY_LENGTH, FILE_MAGIC,
+    MAX_ALIGNMENT_PADDING, MAX_ASSET_LIST_LENGTH, MAX_ENTRIES, MAX_PADDING_WORDS, MAX_PHASES,
+    PHASE_LENGTH,
+};
+use std::convert::TryInto;
+use std::io::Result as IOResult;
+use std::io::{Error as IOError, ErrorKind};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Length of the header up to and including the entry count
+const HEADER_SUMMARY_LENGTH: usize = 128;
+
+/// Length of the timestamp and zero fill, before the file preamble's entries
+const HEADER_TIMESTAMP_LENGTH: usize = 152;
+
+/// Length of the fields between the asset list and its alignment padding
+const HEADER_ASSETS_TRAILER_LENGTH: usize = 6;
+
+/// Length of the header after its alignment padding, up to and including the track name
+const HEADER_TRACK_LENGTH: usize = 211;
+
+/// Length of the fields between the track name and the phase count, inclusive
+const PHASES_PREAMBLE_LENGTH: usize = 132;
+
+/// Length of the fields following the entrant list magic, before the first entrant
+const ENTRANTS_PREAMBLE_LENGTH: usize = 8;
+
+/// A Replay read from an `AsyncRead`
+#[derive(Debug)]
+pub struct AsyncReplay<R: AsyncRead + Unpin> {
+    pub reader: R,
+    pub metadata: Header,
+}
+
+impl<R: AsyncRead + Unpin> AsyncReplay<R> {
+    /// Create a new replay from an AsyncRead
+    pub async fn new(mut r: R) -> IOResult<Self> {
+        validate_reader(&mut r).await?;
+
+        let metadata = read_header(&mut r).await?;
+
+        Ok(AsyncReplay {
+            reader: r,
+            metadata,
+        })
+    }
+
+    /// Create a new replay from an AsyncRead, including its session phases and entrants
+    pub async fn load(r: R) -> IOResult<Self> {
+        let mut replay = Self::new(r).await?;
+        replay.load_entrants().await?;
+
+        Ok(replay)
+    }
+
+    ///
+    /// Load the session phases and entrants into `metadata`, as `Replay::load_entrants`.
+    ///
+    /// These directly follow the header, so this must be called before anything else is read
+    /// from `reader`.
+    pub async fn load_entrants(&mut self) -> IOResult<()> {
+        read_entrants(&mut self.reader, &mut self.metadata).await
+    }
+}
+
+/// Validate the given reader contains replay data.
+///
+/// This function consumes the first 4 bytes of data from the reader.
+pub async fn validate_reader<R: AsyncRead + Unpin>(mut src: R) -> IOResult<()> {
+    let mut magic = [0u8; 4];
+
+    src.read_exact(&mut magic[..]).await?;
+
+    if magic == FILE_MAGIC {
+        Ok(())
+    } else {
+        Err(IOError::new(
+            ErrorKind::InvalidData,
+            "Invalid data at start of stream",
+        ))
+    }
+}
+
+/// Read `length` more bytes onto the end of `data`
+async fn read_more<R: AsyncRead + Unpin>(
+    r: &mut R,
+    data: &mut Vec<u8>,
+    length: usize,
+) -> IOResult<()> {
+    let start = data.len();
+    data.resize(start + length, 0);
+    r.read_exact(&mut data[start..]).await?;
+
+    Ok(())
+}
+
+/// The last word in `data`
+fn last_word(data: &[u8]) -> u32 {
+    u32::from_le_bytes(data[data.len() - 4..].try_into().unwrap())
+}
+
+/// Load Header data from an `AsyncRead`, as `Header::from` does from a `Read`.
+///
+/// The reader is left at the end of the header, at the session phases.
+pub async fn read_header<R: AsyncRead + Unpin>(mut r: R) -> IOResult<Header> {
+    let mut data = vec![];
+
+    read_more(&mut r, &mut data, HEADER_SUMMARY_LENGTH).await?;
+    let entry_count = bounded(last_word(&data), MAX_ENTRIES, "Entry count")?;
+
+    let length = HEADER_TIMESTAMP_LENGTH + entry_count * ENTRY_LENGTH + 4;
+    read_more(&mut r, &mut data, length).await?;
+    let asset_list_length = bounded(last_word(&data), MAX_ASSET_LIST_LENGTH, "Asset list length")?;
+
+    let length = asset_list_length + HEADER_ASSETS_TRAILER_LENGTH;
+    read_more(&mut r, &mut data, length).await?;
+
+    // Spaces up to word alignment, and the first byte of the following block
+    for _ in 0..=MAX_ALIGNMENT_PADDING {
+        read_more(&mut r, &mut data, 1).await?;
+
+        if data[data.len() - 1] != b' ' {
+            break;
+        }
+    }
+
+    if data[data.len() - 1] != b' ' {
+        read_more(&mut r, &mut data, HEADER_TRACK_LENGTH).await?;
+    }
+
+    Header::from(&data[..])
+}
+
+///
+/// Load the session phases and entrants into `header` from an `AsyncRead`, as
+/// `Header::read_entrants` does from a `Read`.
+///
+/// The reader must be at the end of the header, and is left at the start of the body.
+pub async fn read_entrants<R: AsyncRead + Unpin>(mut r: R, header: &mut Header) -> IOResult<()> {
+    let mut data = vec![];
+
+    read_more(&mut r, &mut data, PHASES_PREAMBLE_LENGTH).await?;
+    let phase_count = bounded(last_word(&data), MAX_PHASES, "Phase count")?;
+    read_more(&mut r, &mut data, phase_count * PHASE_LENGTH).await?;
+
+    // Zero padding up to the entrant list, and its magic
+    for _ in 0..=MAX_PADDING_WORDS {
+        read_more(&mut r, &mut data, 4).await?;
+
+        if last_word(&data) != 0 {
+            break;
+        }
+    }
+
+    if data[data.len() - 4..] == *ENTRANTS_MAGIC {
+        let length = ENTRANTS_PREAMBLE_LENGTH + header.entry_count * ENTRANT_LENGTH;
+        read_more(&mut r, &mut data, length).await?;
+    }
+
+    header.read_entrants(&data[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::{fixture, Replay};
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn async_replay() {
+        let data = fixture::replay();
+        let expected = Replay::new(&data[..]).unwrap();
+
+        let replay = block_on(AsyncReplay::new(&data[..])).unwrap();
+        assert_eq!(replay.metadata, expected.metadata);
+
+        // Only the header was read
+        assert_eq!(replay.reader, expected.reader);
+
+        let err = block_on(AsyncReplay::new(&b"YLLR\" THIS ISNT VALID"[..])).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let err = block_on(AsyncReplay::new(&data[..500])).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        let mut corrupt = data.clone();
+        corrupt[128..132].copy_from_slice(&1000u32.to_le_bytes());
+        let err = block_on(AsyncReplay::new(&corrupt[..])).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn async_entrants() {
+        let data = fixture::replay();
+        let expected = Replay::load(&data[..]).unwrap();
+
+        let replay = block_on(AsyncReplay::load(&data[..])).unwrap();
+        assert_eq!(replay.metadata, expected.metadata);
+        assert_eq!(replay.metadata.entries.len(), 3);
+        assert_eq!(replay.reader, expected.reader);
+
+        // Same outcome as the blocking parser when cut short or corrupt
+        let header_length = data.len() - Replay::new(&data[..]).unwrap().reader.len();
+        let body_offset = data.len() - Replay::load(&data[..]).unwrap().reader.len();
+
+        for length in header_length..body_offset {
+            let err = block_on(AsyncReplay::load(&data[..length])).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnexpectedEof, "{} bytes", length);
+        }
+
+        let magic = data.windows(4).position(|w| w == b"SLRD").unwrap();
+        let mut corrupt = data.clone();
+        corrupt[magic] = b'X';
+
+        let mut replay = block_on(AsyncReplay::new(&corrupt[..])).unwrap();
+        let err = block_on(replay.load_entrants()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(replay.metadata.phases.is_empty());
+    }
+}
@@ -0,0 +1,278 @@
+//!
+//! Index of a directory of replay files.
+//!
+//! `ReplayIndex` stores the header of each `.rpy` file found, and can be saved to and loaded from
+//! disk. Rescanning only parses files which are new, or whose modification time or size changed.
+
+use crate::replay::{Header, Replay};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+///
+/// A replay file found by `ReplayIndex`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayRecord {
+    pub path: PathBuf,
+    pub modified: SystemTime,
+    pub size: u64,
+    pub header: Option<Header>, // Replay header, if the file could be parsed
+    pub error: Option<String>,  // Why the file (or its entrant list) could not be parsed
+}
+
+impl ReplayRecord {
+    fn load(path: PathBuf, modified: SystemTime, size: u64) -> Self {
+        let replay = File::open(&path).and_then(|f| Replay::new(BufReader::new(f)));
+
+        // Keep the header even if the phases and entrants can't be read
+        let (header, error) = match replay {
+            Ok(mut replay) => match replay.load_entrants() {
+                Ok(()) => (Some(replay.metadata), None),
+                Err(e) => (Some(replay.metadata), Some(e.to_string())),
+            },
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        ReplayRecord {
+            path,
+            modified,
+            size,
+            header,
+            error,
+        }
+    }
+
+    /// Test if the replay includes a driver, as the creator or an entrant.
+    ///
+    /// `driver` is matched against user IDs, and case-insensitively against names.
+    pub fn has_driver(&self, driver: &str) -> bool {
+        let header = match &self.header {
+            Some(header) => header,
+            None => return false,
+        };
+
+        let matches = |user_id: i64, name: &str| {
+            driver.trim().parse::<i64>().ok() == Some(user_id)
+                || name.to_lowercase().contains(&driver.to_lowercase())
+        };
+
+        matches(header.user_id as i64, &header.user_name)
+            || header
+                .entries
+                .iter()
+                .any(|e| matches(e.driver.user_id as i64, &e.driver.name))
+    }
+}
+
+///
+/// Criteria for `ReplayIndex::search`. Unset criteria match every replay.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayQuery {
+    pub track: Option<String>,  // Track name, e.g. `iowa`
+    pub layout: Option<String>, // Track layout, e.g. `oval`
+    pub driver: Option<String>, // User ID or part of a name, see `ReplayRecord::has_driver`
+    pub phase: Option<String>,  // Session phase the replay must include, e.g. `RACE`
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+}
+
+impl ReplayQuery {
+    pub fn matches(&self, record: &ReplayRecord) -> bool {
+        let header = match &record.header {
+            Some(header) => header,
+            None => return false,
+        };
+
+        let same = |a: &str, b: &str| a.eq_ignore_ascii_case(b);
+
+        self.track
+            .as_deref()
+            .map_or(true, |t| same(t, &header.track))
+            && self.layout.as_deref().map_or(true, |l| {
+                header.layout.as_deref().is_some_and(|h| same(l, h))
+            })
+            && self
+                .phase
+                .as_deref()
+                .map_or(true, |p| header.phase(p).is_some())
+            && self.since.map_or(true, |t| header.timestamp >= t)
+            && self.until.map_or(true, |t| header.timestamp < t)
+            && self
+                .driver
+                .as_deref()
+                .map_or(true, |d| record.has_driver(d))
+    }
+}
+
+///
+/// Index of the `.rpy` files in a directory tree (usually `Documents/iRacing/replay`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayIndex {
+    pub replays: Vec<ReplayRecord>,
+}
+
+impl ReplayIndex {
+    ///
+    /// Load an index saved with `save`, or an empty index if there is no file at `path`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        match File::open(path) {
+            Ok(f) => serde_json::from_reader(BufReader::new(f))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Save the index to `path`
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, self).map_err(io::Error::from)
+    }
+
+    ///
+    /// Scan `root` for `.rpy` files, bringing the index up to date.
+    ///
+    /// Files with the same modification time and size as when they were last indexed are not
+    /// parsed again, and files which no longer exist are removed. Returns the number of files parsed.
+    ///
+    /// If the directory tree can't be read the error is returned and the index is left unchanged.
+    pub fn scan(&mut self, root: &Path) -> io::Result<usize> {
+        let mut known: BTreeMap<&Path, &ReplayRecord> =
+            self.replays.iter().map(|r| (r.path.as_path(), r)).collect();
+        let mut replays = vec![];
+        let mut pending = vec![root.to_path_buf()];
+        let mut parsed = 0;
+
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                let metadata = entry.metadata()?;
+
+                if metadata.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+
+                if !path
+                    .extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("rpy"))
+                {
+                    continue;
+                }
+
+                let modified = metadata.modified()?;
+                let size = metadata.len();
+
+                let record = match known.remove(path.as_path()) {
+                    Some(r) if r.modified == modified && r.size == size => r.clone(),
+                    _ => {
+                        parsed += 1;
+                        ReplayRecord::load(path, modified, size)
+                    }
+                };
+
+                replays.push(record);
+            }
+        }
+
+        replays.sort_by(|a, b| a.path.cmp(&b.path));
+        self.replays = replays;
+        Ok(parsed)
+    }
+
+    /// Replays matching a query, oldest first
+    pub fn search(&self, query: &ReplayQuery) -> Vec<&ReplayRecord> {
+        let mut found: Vec<_> = self.replays.iter().filter(|r| query.matches(r)).collect();
+
+        found.sort_by_key(|r| r.header.as_ref().map(|h| h.timestamp));
+        found
+    }
+
+    /// Replays which could not be parsed, in full or in part
+    pub fn errors(&self) -> impl Iterator<Item = &ReplayRecord> {
+        self.replays.iter().filter(|r| r.error.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::fixture;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn index_and_search() {
+        let root = std::env::temp_dir().join(format!("iracing-replays-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("2021")).unwrap();
+
+        let data = fixture::replay();
+        fs::write(root.join("2021/subses36491425.rpy"), &data).unwrap();
+        fs::write(root.join("broken.rpy"), &data[..100]).unwrap();
+        fs::write(root.join("no_entrants.rpy"), &data[..1000]).unwrap();
+        fs::write(root.join("notes.txt"), b"not a replay").unwrap();
+
+        let index_path = root.join("index.json");
+        let mut index = ReplayIndex::open(&index_path).unwrap();
+        assert_eq!(index.scan(&root).unwrap(), 3);
+        assert_eq!(index.replays.len(), 3);
+        assert_eq!(index.errors().count(), 2);
+
+        // A replay cut short in its entrant list still has its header
+        let partial = &index.replays[2];
+        assert!(partial.path.ends_with("no_entrants.rpy"));
+        assert_eq!(partial.header.as_ref().unwrap().session_id, 36491425);
+        assert!(partial.error.is_some());
+        index.save(&index_path).unwrap();
+
+        // Unchanged files are not parsed again
+        let mut index = ReplayIndex::open(&index_path).unwrap();
+        assert_eq!(index.scan(&root).unwrap(), 0);
+
+        fs::write(root.join("broken.rpy"), &data).unwrap();
+        fs::remove_file(root.join("2021/subses36491425.rpy")).unwrap();
+        fs::remove_file(root.join("no_entrants.rpy")).unwrap();
+        assert_eq!(index.scan(&root).unwrap(), 1);
+        assert_eq!(index.replays.len(), 1);
+        assert_eq!(index.errors().count(), 0);
+
+        // A failed scan keeps the index
+        assert!(index.scan(&root.join("missing")).is_err());
+        assert_eq!(index.replays.len(), 1);
+
+        let march = NaiveDate::from_ymd_opt(2021, 3, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        let mut query = ReplayQuery {
+            track: Some("Iowa".to_owned()),
+            layout: Some("oval".to_owned()),
+            driver: Some("m\u{fc}ller".to_owned()),
+            phase: Some("race".to_owned()),
+            since: Some(march),
+            until: None,
+        };
+
+        let found = index.search(&query);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].header.as_ref().unwrap().session_id, 36491425);
+
+        query.driver = Some("315629".to_owned());
+        assert_eq!(index.search(&query).len(), 1);
+
+        query.driver = Some("Someone Else".to_owned());
+        assert!(index.search(&query).is_empty());
+
+        query.driver = None;
+        query.until = Some(march);
+        assert!(index.search(&query).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
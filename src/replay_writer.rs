@@ -0,0 +1,335 @@
+//!
+//! Rewriting replay headers, e.g. to pseudonymize the drivers before sharing a replay.
+//!
+//! The header is copied byte-for-byte apart from the rewritten fields: the creator's user ID and
+//! name, the user IDs of the entries in the file preamble, and the user ID and name of each
+//! entrant in the entrant list.
+//!
+//! **Only the header is pseudonymized.** The body is copied unchanged, as its layout is not known.
+//! In real replays the body also carries the session info, with the `UserName`, `UserID` and
+//! `TeamName` of every driver, so an anonymized replay can still identify the drivers.
+
+use crate::replay::{validate_reader, Header, Replay, ENTRANT_LENGTH, ENTRY_LENGTH};
+use siphasher::sip::SipHasher24;
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+use std::io::{self, Read, Write};
+use std::io::{Error as IOError, ErrorKind, Result as IOResult};
+
+/// Offset of the creator's user ID
+const USER_ID_OFFSET: usize = 44;
+
+/// Offset of the creator's name
+const USER_NAME_OFFSET: usize = 56;
+
+/// Offset of the first entry in the file preamble
+const PREAMBLE_ENTRIES_OFFSET: usize = 284;
+
+/// Length of a name field
+const NAME_LENGTH: usize = 64;
+
+/// Offset of the user ID within an entrant record
+const ENTRANT_USER_ID_OFFSET: usize = 16;
+
+/// Offset of the driver's name within an entrant record
+const ENTRANT_NAME_OFFSET: usize = 28;
+
+/// The replacement identity of a user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pseudonym {
+    pub user_id: i32,
+    pub name: String,
+}
+
+///
+/// Rewrites the user IDs and names in a replay.
+///
+/// Users in the mapping table get the given pseudonym. All other users get a pseudonym derived from
+/// a keyed hash (SipHash-2-4) of their user ID, so the same key gives the same pseudonyms across
+/// replays, while pseudonyms can't be reversed without the key. Hashed user IDs are negative
+/// (from -2 down), so they can't be mistaken for real users. User IDs of zero or less (e.g. the
+/// pace car, -1) are left alone.
+///
+/// Note the creator's user ID is read back as a `u32` by `Header`, so a negative pseudonym
+/// appears there as `user_id as u32`.
+#[derive(Debug, Clone, Default)]
+pub struct Anonymizer {
+    key: [u8; 16],
+    mapping: BTreeMap<i32, Pseudonym>,
+}
+
+impl Anonymizer {
+    pub fn new(key: [u8; 16]) -> Self {
+        Anonymizer {
+            key,
+            mapping: BTreeMap::new(),
+        }
+    }
+
+    /// Use the given pseudonym for a user, instead of a hashed one
+    pub fn insert(&mut self, user_id: i32, pseudonym: Pseudonym) {
+        self.mapping.insert(user_id, pseudonym);
+    }
+
+    /// The pseudonym of a user, or `None` if the user is left alone
+    pub fn pseudonym(&self, user_id: i32) -> Option<Pseudonym> {
+        if user_id <= 0 {
+            return None;
+        }
+
+        if let Some(pseudonym) = self.mapping.get(&user_id) {
+            return Some(pseudonym.clone());
+        }
+
+        let mut hasher = SipHasher24::new_with_key(&self.key);
+        hasher.write_i32(user_id);
+
+        // -2 to -i32::MAX, clear of real user IDs and the pace car
+        let id = -((hasher.finish() % (i32::MAX as u64 - 1)) as i32) - 2;
+
+        Some(Pseudonym {
+            user_id: id,
+            name: format!("Driver {}", id.unsigned_abs()),
+        })
+    }
+
+    ///
+    /// Check that no two users end up with the same user ID.
+    ///
+    /// This includes users left alone, so a pseudonym can't take the ID of another user either.
+    fn check_collisions<I: IntoIterator<Item = i32>>(&self, user_ids: I) -> IOResult<()> {
+        let mut assigned: BTreeMap<i32, i32> = BTreeMap::new();
+
+        for user_id in user_ids {
+            let id = self.pseudonym(user_id).map_or(user_id, |p| p.user_id);
+
+            match assigned.insert(id, user_id) {
+                Some(other) if other != user_id => {
+                    return Err(IOError::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Users {} and {} would both have user ID {}",
+                            other, user_id, id
+                        ),
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Copy a replay from `r` to `w`, rewriting the user IDs and names in its header.
+    ///
+    /// The rewritten header is parsed again before anything is written, and returned.
+    /// The body is copied unchanged, including the names and user IDs in its session info,
+    /// so the output still identifies the drivers to anyone who reads the body.
+    pub fn anonymize<R: Read, W: Write>(&self, mut r: R, mut w: W) -> IOResult<Header> {
+        let mut recorder = Recorder {
+            inner: &mut r,
+            data: vec![],
+        };
+
+        validate_reader(&mut recorder)?;
+        let mut header = Header::from(&mut recorder)?;
+        header.read_entrants(&mut recorder)?;
+        let mut data = recorder.data;
+
+        let entrants_offset = data.len() - header.entries.len() * ENTRANT_LENGTH;
+
+        let preamble_ids: Vec<i32> = (0..header.entries.len())
+            .map(|n| read_word(&data, PREAMBLE_ENTRIES_OFFSET + n * ENTRY_LENGTH))
+            .collect();
+
+        self.check_collisions(
+            std::iter::once(header.user_id as i32)
+                .chain(preamble_ids.iter().copied())
+                .chain(header.entries.iter().map(|e| e.id)),
+        )?;
+
+        if let Some(p) = self.pseudonym(header.user_id as i32) {
+            write_word(&mut data, USER_ID_OFFSET, p.user_id);
+            write_name(&mut data, USER_NAME_OFFSET, &p.name);
+        }
+
+        for (n, &user_id) in preamble_ids.iter().enumerate() {
+            if let Some(p) = self.pseudonym(user_id) {
+                write_word(
+                    &mut data,
+                    PREAMBLE_ENTRIES_OFFSET + n * ENTRY_LENGTH,
+                    p.user_id,
+                );
+            }
+        }
+
+        for (n, entry) in header.entries.iter().enumerate() {
+            let offset = entrants_offset + n * ENTRANT_LENGTH;
+
+            if let Some(p) = self.pseudonym(entry.id) {
+                write_word(&mut data, offset + ENTRANT_USER_ID_OFFSET, p.user_id);
+                write_name(&mut data, offset + ENTRANT_NAME_OFFSET, &p.name);
+            }
+        }
+
+        let rewritten = Replay::load(&data[..])?.metadata;
+
+        if rewritten.entries.len() != header.entries.len()
+            || rewritten.session_id != header.session_id
+        {
+            return Err(IOError::new(
+                ErrorKind::InvalidData,
+                "Rewritten replay header does not match the original",
+            ));
+        }
+
+        w.write_all(&data)?;
+        io::copy(&mut r, &mut w)?;
+
+        Ok(rewritten)
+    }
+}
+
+/// Passes reads through, keeping a copy of everything read
+struct Recorder<R: Read> {
+    inner: R,
+    data: Vec<u8>,
+}
+
+impl<R: Read> Read for Recorder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> IOResult<usize> {
+        let n = self.inner.read(buf)?;
+        self.data.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
+fn read_word(data: &[u8], offset: usize) -> i32 {
+    let mut word = [0u8; 4];
+    word.copy_from_slice(&data[offset..offset + 4]);
+    i32::from_le_bytes(word)
+}
+
+fn write_word(data: &mut [u8], offset: usize, value: i32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Write a NUL-padded Latin-1 name, replacing characters outside of Latin-1 with `?`
+fn write_name(data: &mut [u8], offset: usize, name: &str) {
+    let field = &mut data[offset..offset + NAME_LENGTH];
+    field.iter_mut().for_each(|b| *b = 0);
+
+    for (b, c) in field[..NAME_LENGTH - 1].iter_mut().zip(name.chars()) {
+        *b = if (c as u32) < 256 {
+            c as u32 as u8
+        } else {
+            b'?'
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::fixture;
+
+    #[test]
+    fn anonymize() {
+        let data = fixture::replay();
+
+        let mut anonymizer = Anonymizer::new([42; 16]);
+        anonymizer.insert(
+            81797,
+            Pseudonym {
+                user_id: 1,
+                name: "Replay Author".to_owned(),
+            },
+        );
+
+        let mut out = vec![];
+        let header = anonymizer.anonymize(&data[..], &mut out).unwrap();
+
+        assert_eq!(out.len(), data.len());
+        assert_eq!(header.user_id, 1);
+        assert_eq!(header.user_name, "Replay Author");
+        assert_eq!(header.entries[0].driver.name, "Replay Author");
+
+        let hashed = anonymizer.pseudonym(332748).unwrap();
+        assert!(hashed.user_id < -1);
+        assert_eq!(header.entries[2].id, hashed.user_id);
+        assert_eq!(header.entries[2].driver.name, hashed.name);
+        assert_eq!(
+            Anonymizer::new([42; 16]).pseudonym(332748),
+            Some(hashed.clone())
+        );
+        assert_ne!(Anonymizer::new([7; 16]).pseudonym(332748), Some(hashed));
+        assert_eq!(anonymizer.pseudonym(-1), None);
+
+        // Only the rewritten fields differ
+        let original = Replay::load(&data[..]).unwrap().metadata;
+        let mut restored = header.clone();
+        restored.user_id = original.user_id;
+        restored.user_name = original.user_name.clone();
+
+        for (entry, original) in restored.entries.iter_mut().zip(original.entries.iter()) {
+            entry.id = original.id;
+            entry.driver = original.driver.clone();
+        }
+
+        assert_eq!(restored, original);
+
+        // The body is copied unchanged
+        let body = original.phases[0].offset as usize;
+        assert_eq!(out[body..], data[body..]);
+        assert_eq!(read_word(&out, PREAMBLE_ENTRIES_OFFSET), 1);
+
+        // Every changed byte is within a rewritten field
+        let entrants = body - 3 * ENTRANT_LENGTH;
+        let mut fields = vec![
+            USER_ID_OFFSET..USER_ID_OFFSET + 4,
+            USER_NAME_OFFSET..USER_NAME_OFFSET + NAME_LENGTH,
+        ];
+
+        for n in 0..3 {
+            let preamble = PREAMBLE_ENTRIES_OFFSET + n * ENTRY_LENGTH;
+            let entrant = entrants + n * ENTRANT_LENGTH;
+            fields.push(preamble..preamble + 4);
+            let user_id = entrant + ENTRANT_USER_ID_OFFSET;
+            let name = entrant + ENTRANT_NAME_OFFSET;
+            fields.push(user_id..user_id + 4);
+            fields.push(name..name + NAME_LENGTH);
+        }
+
+        assert!((0..body)
+            .filter(|&i| out[i] != data[i])
+            .all(|i| fields.iter().any(|f| f.contains(&i))));
+    }
+
+    #[test]
+    fn collisions() {
+        let data = fixture::replay();
+        let hashed = Anonymizer::new([42; 16]).pseudonym(332748).unwrap();
+
+        // A mapped pseudonym taking the ID of a hashed one
+        let mut anonymizer = Anonymizer::new([42; 16]);
+        anonymizer.insert(81797, hashed);
+
+        let mut out = vec![];
+        let err = anonymizer.anonymize(&data[..], &mut out).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(out.is_empty());
+
+        // ... or the ID of a user who is not rewritten
+        let mut anonymizer = Anonymizer::new([42; 16]);
+        anonymizer.insert(
+            81797,
+            Pseudonym {
+                user_id: -1,
+                name: "Pace Car".to_owned(),
+            },
+        );
+        assert!(anonymizer.check_collisions(vec![81797, -1]).is_err());
+        assert!(anonymizer.check_collisions(vec![81797, 81797, 0]).is_ok());
+    }
+}
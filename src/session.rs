@@ -1,10 +1,15 @@
+use crate::quantity::{Dimension, Quantity};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
 
 ///
 /// Session Details
 ///
 /// Top-level details regarding the current session, including race weekend, session and drivers.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SessionDetails {
     #[serde(rename = "WeekendInfo")]
     pub weekend: WeekendInfo, // Race Weekend Info (track, location, series etc.)
@@ -14,12 +19,30 @@ pub struct SessionDetails {
 
     #[serde(rename = "DriverInfo")]
     pub drivers: DriverInfo, // Driver information
+
+    #[serde(rename = "QualifyResultsInfo")]
+    pub qualify_results: Option<QualifyResultsInfo>, // Results of qualifying, once it has run
+
+    #[serde(rename = "CameraInfo")]
+    pub cameras: Option<CameraInfo>, // Camera groups available
+
+    #[serde(rename = "RadioInfo")]
+    pub radios: Option<RadioInfo>, // Radios and their frequencies
+
+    #[serde(rename = "SplitTimeInfo")]
+    pub split_times: Option<SplitTimeInfo>, // Track sectors
+
+    #[serde(rename = "CarSetup")]
+    pub car_setup: Option<CarSetup>, // Player's car setup, only present when driving
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
 }
 
 ///
 /// Details of the race weekend. Including details of the track being raced,
 /// the weather, racing series, and the rules in play for the session.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct WeekendInfo {
     pub track_name: String, // Track Name
@@ -27,16 +50,16 @@ pub struct WeekendInfo {
     #[serde(rename = "TrackID")]
     pub track_id: u32, // iRacing Track ID
 
-    pub track_length: String,             // Track length (as string of km)
-    pub track_display_name: String,       // Track display name
-    pub track_display_short_name: String, // Track short display name
-    pub track_config_name: String,        // Track layout/configuration name
-    pub track_city: String,               // Track Location, City
-    pub track_country: String,            // Track Location: Country
-    pub track_altitude: String,           // Track Altitude (m)
-    pub track_latitude: String,           // Track Latitude (deg)
-    pub track_longitude: String,          // Track Longitude (deg)
-    pub track_north_offset: String,       // Track rotation relative to true north (rad)
+    pub track_length: String,              // Track length (as string of km)
+    pub track_display_name: String,        // Track display name
+    pub track_display_short_name: String,  // Track short display name
+    pub track_config_name: Option<String>, // Track layout/configuration name
+    pub track_city: String,                // Track Location, City
+    pub track_country: String,             // Track Location: Country
+    pub track_altitude: String,            // Track Altitude (m)
+    pub track_latitude: String,            // Track Latitude (deg)
+    pub track_longitude: String,           // Track Longitude (deg)
+    pub track_north_offset: String,        // Track rotation relative to true north (rad)
 
     #[serde(rename = "TrackNumTurns")]
     pub track_turns: u32, // Number of turns
@@ -62,11 +85,11 @@ pub struct WeekendInfo {
 
     #[serde(rename = "TrackWindDir")]
     pub track_wind_direction: String, // Track wind direction relative to north (rad)
-    pub track_fog_level: String, // Track fogginess
-    pub track_cleanup: i32,      // Track cleanup
+    pub track_fog_level: String,    // Track fogginess
+    pub track_cleanup: Option<i32>, // Track cleanup
 
     #[serde(rename = "TrackDynamicTrack")]
-    pub track_dynamic: i32, // Track Dynamic
+    pub track_dynamic: Option<i32>, // Track Dynamic
 
     #[serde(rename = "SeriesID")]
     pub series_id: i32, // iRacing series ID
@@ -92,20 +115,23 @@ pub struct WeekendInfo {
     pub max_drivers: i8,    // Maximum drivers per team
 
     #[serde(rename = "DCRuleSet")]
-    pub dc_rule_set: String, // Driver change rules
+    pub dc_rule_set: Option<String>, // Driver change rules
 
-    pub qualifier_must_start_race: i8, // Qualifying driver must start race
+    pub qualifier_must_start_race: Option<i8>, // Qualifying driver must start race
 
     #[serde(rename = "NumCarClasses")]
     pub n_classes: u32, // Number of classes in the race
     #[serde(rename = "NumCarTypes")]
-    pub n_car_types: u32, // Number of car types eligible for the race
+    pub n_car_types: Option<u32>, // Number of car types eligible for the race
 
     #[serde(rename = "WeekendOptions")]
     pub options: WeekendOptions,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct WeekendOptions {
     #[serde(rename = "NumStarters")]
@@ -130,20 +156,26 @@ pub struct WeekendOptions {
     pub commercial_mode: String, // On if race is being run commercially (e.g. Professional race)
     pub night_mode: String,      // On if race is at night
     pub is_fixed_setup: i8,      // On if car setups are fixed by series rules
-    pub strict_laps_checking: String,
-    pub has_open_registration: i8, // On if anyone can register, off if registration requires a specific license or invitation.
-    pub hardcore_level: i8,        // Hardcoreness
+    pub strict_laps_checking: Option<String>,
+    pub has_open_registration: Option<i8>, // On if anyone can register, off if registration requires a specific license or invitation.
+    pub hardcore_level: Option<i8>,        // Hardcoreness
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct SessionInfo {
     #[serde(rename = "NumSessions")]
     pub n_sessions: Option<u32>, // Number of sessions (possibly None)
     pub sessions: Vec<Session>, // Sessions
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Session {
     #[serde(rename = "SessionNum")]
@@ -160,11 +192,56 @@ pub struct Session {
     #[serde(rename = "SessionTrackRubberState")]
     pub track_rubber_state: String,
 
+    #[serde(rename = "SessionNumLapsToAvg")]
+    pub laps_to_average: Option<u32>, // Number of laps averaged to rank qualifying
+
+    pub session_name: Option<String>,
+    pub session_sub_type: Option<String>,
+    pub session_skipped: Option<i8>,
+    pub session_run_groups_used: Option<i8>,
+
     #[serde(rename = "ResultsPositions")]
     pub results: Option<Vec<SessionResult>>,
+
+    #[serde(rename = "ResultsFastestLap")]
+    pub fastest_laps: Option<Vec<FastestLap>>,
+
+    #[serde(rename = "ResultsAverageLapTime")]
+    pub average_lap_time: Option<f32>, // Average lap time (s), -1 if not yet known
+
+    #[serde(rename = "ResultsNumCautionFlags")]
+    pub caution_flags: Option<i32>,
+
+    #[serde(rename = "ResultsNumCautionLaps")]
+    pub caution_laps: Option<i32>,
+
+    #[serde(rename = "ResultsNumLeadChanges")]
+    pub lead_changes: Option<i32>,
+
+    #[serde(rename = "ResultsLapsComplete")]
+    pub laps_complete: Option<i32>, // Laps completed by the leader, -1 if not yet known
+
+    #[serde(rename = "ResultsOfficial")]
+    pub official: Option<i8>, // Results are official
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Fastest lap of a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct FastestLap {
+    pub car_idx: i32,
+    pub fastest_lap: i32,  // Lap number
+    pub fastest_time: f32, // Lap time (s)
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct SessionResult {
     pub position: i32,
@@ -177,10 +254,179 @@ pub struct SessionResult {
     pub last_time: f32,
     pub laps_led: i32,
     pub laps_complete: i32,
+    pub joker_laps_complete: Option<i32>,
     pub laps_driven: f32,
     pub incidents: i32,
     pub reason_out_id: i32,
     pub reason_out_str: String,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+///
+/// Results of qualifying, carried into the following sessions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct QualifyResultsInfo {
+    pub results: Vec<QualifyResult>,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct QualifyResult {
+    pub position: i32, // Position (0-based)
+    pub class_position: i32,
+    pub car_idx: i32,
+    pub fastest_lap: i32,
+    pub fastest_time: f32, // Lap time (s)
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+///
+/// Camera groups available in the session, and the cameras in each group.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CameraInfo {
+    pub groups: Vec<CameraGroup>,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CameraGroup {
+    #[serde(rename = "GroupNum")]
+    pub number: i32,
+
+    #[serde(rename = "GroupName")]
+    pub name: String,
+
+    #[serde(default)]
+    pub is_scenic: bool, // Scenic cameras don't follow a car
+
+    pub cameras: Vec<Camera>,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Camera {
+    #[serde(rename = "CameraNum")]
+    pub number: i32,
+
+    #[serde(rename = "CameraName")]
+    pub name: String,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+///
+/// Radios available to the player, and the frequencies of each.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RadioInfo {
+    #[serde(rename = "SelectedRadioNum")]
+    pub selected_radio: i32,
+
+    pub radios: Vec<Radio>,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Radio {
+    #[serde(rename = "RadioNum")]
+    pub number: i32,
+
+    pub hop_count: i32,
+
+    #[serde(rename = "NumFrequencies")]
+    pub n_frequencies: u32,
+
+    #[serde(rename = "TunedToFrequencyNum")]
+    pub tuned_frequency: i32,
+
+    pub scanning_is_on: i8,
+    pub frequencies: Vec<RadioFrequency>,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RadioFrequency {
+    #[serde(rename = "FrequencyNum")]
+    pub number: i32,
+
+    #[serde(rename = "FrequencyName")]
+    pub name: String, // e.g. @ALLTEAMS, @DRIVERS, @TEAM
+
+    pub priority: i32,
+    pub car_idx: i32,   // Car the frequency belongs to, -1 for none
+    pub entry_idx: i32, // Entry the frequency belongs to, -1 for none
+
+    #[serde(rename = "ClubID")]
+    pub club_id: i32,
+
+    pub can_scan: i8,
+    pub can_squawk: i8,
+    pub muted: i8,
+    pub is_mutable: i8,
+    pub is_deletable: i8,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+///
+/// Track sectors used for split times.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SplitTimeInfo {
+    pub sectors: Vec<Sector>,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Sector {
+    #[serde(rename = "SectorNum")]
+    pub number: u32,
+
+    #[serde(rename = "SectorStartPct")]
+    pub start_percent: f32, // Start of the sector as a fraction of a lap
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+///
+/// The player's car setup.
+///
+/// The layout of the setup differs for every car, so the sections are kept in `extra` as they appear
+/// in the session info. `update_count` increments each time the setup is changed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CarSetup {
+    pub update_count: u32,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Setup sections, e.g. TiresAero, Chassis
 }
 
 ///
@@ -188,7 +434,7 @@ pub struct SessionResult {
 ///
 /// Struct contains player driver information, as well as a vector of
 /// other drivers in the session
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DriverInfo {
     #[serde(rename = "DriverCarIdx")]
     pub car_index: usize, // Drivers' Car Index
@@ -246,21 +492,24 @@ pub struct DriverInfo {
 
     #[serde(rename = "Drivers")]
     pub other_drivers: Vec<Driver>,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
 }
 
 ///
 /// Details of all drivers (players) in the session, including the current driver.
 ///
 /// Contains details of the user-profile of the driver, their License class, Safety Rating, and iRating.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Driver {
     #[serde(rename = "CarIdx")]
     pub index: usize,
 
     pub user_name: String,
-    pub abbrev_name: String,
-    pub initials: String,
+    pub abbrev_name: Option<String>, // Not present for the pace car
+    pub initials: Option<String>,    // Not present for the pace car
 
     #[serde(rename = "UserID")]
     pub user_id: i64,
@@ -283,7 +532,7 @@ pub struct Driver {
 
     pub car_screen_name: String,
     pub car_screen_name_short: String,
-    pub car_class_short_name: String,
+    pub car_class_short_name: Option<String>, // Not present for single-class sessions
 
     #[serde(rename = "CarClassRelSpeed")]
     pub car_class_relative_speed: i64,
@@ -321,6 +570,164 @@ pub struct Driver {
 
     pub club_name: Option<String>, // User's club name - Not present for safety car.
     pub division_name: Option<String>, // User's disivision name - Not present for safety car.
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>, // Keys not known to this struct
+}
+
+/// Magic number specifying an unlimited number of laps
+pub const UNLIMITED_LAPS: i32 = 32767;
+
+/// Magic number specifying unlimited time
+pub const UNLIMITED_TIME: f32 = 604800.0;
+
+///
+/// How a session ends: after a number of laps, after a length of time, whichever
+/// of the two comes first, or not at all.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SessionLimit {
+    Unlimited,
+    Laps(u32),
+    Time(f64), // Seconds
+    LapsOrTime { laps: u32, time: f64 },
+}
+
+///
+/// Which of the session limits ends the race
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionEnd {
+    Laps,
+    Time,
+}
+
+///
+/// Predicted end of a session for the leader
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SessionEndPrediction {
+    pub ends_on: SessionEnd,
+    pub total_laps: u32, // Laps the leader will complete in total
+    pub laps_to_go: f64, // Laps the leader has left to drive
+    pub time_to_go: f64, // Seconds until the leader takes the chequered flag
+}
+
+impl SessionLimit {
+    ///
+    /// Build a limit from the optional laps and time limits, treating the
+    /// `UNLIMITED_LAPS` and `UNLIMITED_TIME` magic numbers as no limit.
+    pub fn new(laps: Option<u32>, time: Option<f64>) -> Self {
+        let laps = laps.filter(|&l| l < UNLIMITED_LAPS as u32);
+        let time = time.filter(|&t| t < UNLIMITED_TIME as f64);
+
+        match (laps, time) {
+            (None, None) => SessionLimit::Unlimited,
+            (Some(laps), None) => SessionLimit::Laps(laps),
+            (None, Some(time)) => SessionLimit::Time(time),
+            (Some(laps), Some(time)) => SessionLimit::LapsOrTime { laps, time },
+        }
+    }
+
+    ///
+    /// Build the limit implied by the live `SessionLapsRemainEx` and `SessionTimeRemain` values.
+    ///
+    /// `SessionTimeRemain` goes negative once the time has run out, which is clamped to zero.
+    pub fn from_remaining(laps_remaining: i32, time_remaining: f64) -> Self {
+        Self::new(
+            (laps_remaining >= 0).then_some(laps_remaining as u32),
+            Some(time_remaining.max(0.0)),
+        )
+    }
+
+    pub fn laps(&self) -> Option<u32> {
+        match *self {
+            SessionLimit::Laps(laps) | SessionLimit::LapsOrTime { laps, .. } => Some(laps),
+            _ => None,
+        }
+    }
+
+    pub fn time(&self) -> Option<f64> {
+        match *self {
+            SessionLimit::Time(time) | SessionLimit::LapsOrTime { time, .. } => Some(time),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Check the live `SessionLapsRemainEx` and `SessionTimeRemain` values agree with this limit.
+    ///
+    /// The same kinds of limit must be in play, and neither remaining value may exceed the limit.
+    pub fn is_consistent_with(&self, laps_remaining: i32, time_remaining: f64) -> bool {
+        let remaining = Self::from_remaining(laps_remaining, time_remaining);
+
+        let laps_ok = match (self.laps(), remaining.laps()) {
+            (Some(limit), Some(left)) => left <= limit,
+            (None, None) => true,
+            _ => false,
+        };
+
+        let time_ok = match (self.time(), remaining.time()) {
+            (Some(limit), Some(left)) => left <= limit,
+            (None, None) => true,
+            _ => false,
+        };
+
+        laps_ok && time_ok
+    }
+
+    ///
+    /// Predict whether the session will end on laps or on time.
+    ///
+    /// `leader_progress` is the number of laps the leader has driven, including the fraction
+    /// of the current lap. `time_remaining` is the time left on the session clock in seconds,
+    /// and `leader_pace` the leader's expected lap time in seconds.
+    ///
+    /// When the clock runs out the leader finishes the lap they are on.
+    /// Returns `None` for unlimited sessions, or if the pace is not positive.
+    pub fn predict_end(
+        &self,
+        leader_progress: f64,
+        time_remaining: f64,
+        leader_pace: f64,
+    ) -> Option<SessionEndPrediction> {
+        if leader_pace <= 0.0 {
+            return None;
+        }
+
+        let on_laps = |laps: u32| SessionEndPrediction {
+            ends_on: SessionEnd::Laps,
+            total_laps: laps,
+            laps_to_go: (laps as f64 - leader_progress).max(0.0),
+            time_to_go: (laps as f64 - leader_progress).max(0.0) * leader_pace,
+        };
+
+        let on_time = || {
+            let time_remaining = time_remaining.max(0.0);
+            let total_laps = (leader_progress + time_remaining / leader_pace).ceil();
+            let total_laps = total_laps.max(leader_progress.ceil());
+
+            SessionEndPrediction {
+                ends_on: SessionEnd::Time,
+                total_laps: total_laps as u32,
+                laps_to_go: total_laps - leader_progress,
+                time_to_go: (total_laps - leader_progress) * leader_pace,
+            }
+        };
+
+        match *self {
+            SessionLimit::Unlimited => None,
+            SessionLimit::Laps(laps) => Some(on_laps(laps)),
+            SessionLimit::Time(_) => Some(on_time()),
+            SessionLimit::LapsOrTime { laps, .. } => {
+                let by_laps = on_laps(laps);
+                let by_time = on_time();
+
+                if by_laps.total_laps <= by_time.total_laps {
+                    Some(by_laps)
+                } else {
+                    Some(by_time)
+                }
+            }
+        }
+    }
 }
 
 impl Session {
@@ -332,4 +739,726 @@ impl Session {
     pub fn max_laps(&self) -> Option<u64> {
         self.laps.as_u64()
     }
+
+    ///
+    /// Time limit of the session in seconds, or None if the time is unlimited.
+    pub fn max_time(&self) -> Result<Option<f64>, SessionValueError> {
+        if self.time.trim() == "unlimited" {
+            return Ok(None);
+        }
+
+        base_value("SessionTime", &self.time, Dimension::Time).map(Some)
+    }
+
+    ///
+    /// Laps and time limit of the session.
+    pub fn limit(&self) -> Result<SessionLimit, SessionValueError> {
+        let laps = match &self.laps {
+            serde_yaml::Value::Number(n) => n.as_u64(),
+            serde_yaml::Value::String(s) if s == "unlimited" => None,
+            other => {
+                return Err(SessionValueError {
+                    field: "SessionLaps",
+                    value: serde_yaml::to_string(other)
+                        .unwrap_or_default()
+                        .trim_start_matches("---")
+                        .trim()
+                        .to_owned(),
+                })
+            }
+        };
+
+        Ok(SessionLimit::new(
+            laps.map(|l| l.min(u32::MAX as u64) as u32),
+            self.max_time()?,
+        ))
+    }
+}
+
+impl SessionDetails {
+    ///
+    /// Parse session details from the session info YAML.
+    ///
+    /// The YAML is passed through `sanitize_yaml` first, so names which iRacing
+    /// writes without quoting do not cause the whole document to be rejected.
+    /// Keys which are not known are kept in the `extra` map of the struct they appear in.
+    pub fn from_yaml(yaml: &str) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::from_str(&sanitize_yaml(yaml))
+    }
+
+    ///
+    /// Parse session details, skipping over values which can't be parsed.
+    ///
+    /// Each value which is invalid, or has an unexpected type, is dropped along with anything
+    /// nested below it, and a `SessionWarning` is returned for it. Optional fields which are
+    /// dropped are left as `None`; when a required field is dropped, the driver, session or
+    /// other entry it belongs to is dropped as well.
+    /// Returns an error only if the document still can't be parsed once nothing more can be dropped.
+    pub fn from_yaml_lenient(yaml: &str) -> Result<(Self, Vec<SessionWarning>), serde_yaml::Error> {
+        let sanitized = sanitize_yaml(yaml);
+
+        // Lines of the document, and their line number in the original YAML
+        let mut lines: Vec<(usize, &str)> = sanitized
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l))
+            .collect();
+        let mut warnings = vec![];
+
+        loop {
+            let text = lines.iter().map(|(_, l)| *l).collect::<Vec<_>>().join("\n");
+
+            let error = match serde_yaml::from_str::<SessionDetails>(&text) {
+                Ok(details) => return Ok((details, warnings)),
+                Err(e) => e,
+            };
+
+            let mut index = match error.location() {
+                Some(location) if location.line() >= 1 && location.line() <= lines.len() => {
+                    location.line() - 1
+                }
+                _ => return Err(error),
+            };
+
+            // A missing field is reported at the first line of the mapping it is missing from,
+            // so the whole mapping is dropped: either the sequence item, or the entry holding it
+            let missing = error.to_string().contains("missing field");
+            let item = lines[index].1.trim_start().starts_with("- ");
+
+            if missing && !item {
+                index = match parent(&lines, index) {
+                    Some(parent) => parent,
+                    None => return Err(error),
+                };
+            }
+
+            warnings.push(SessionWarning {
+                line: lines[index].0,
+                message: error.to_string(),
+            });
+
+            if missing && item {
+                drop_item(&mut lines, index);
+            } else {
+                drop_entry(&mut lines, index);
+            }
+        }
+    }
+}
+
+///
+/// A value which was skipped by `SessionDetails::from_yaml_lenient`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionWarning {
+    pub line: usize,     // Line of the session YAML the value was on (1-based)
+    pub message: String, // Why the value was skipped
+}
+
+impl fmt::Display for SessionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Skipped line {}: {}", self.line, self.message)
+    }
+}
+
+/// Remove the entry on line `index`, and any lines nested below it.
+///
+/// When the entry starts an item of a sequence (`- Key: value`) the `- ` is kept,
+/// so the rest of the item is not joined to the previous one.
+fn drop_entry(lines: &mut Vec<(usize, &str)>, index: usize) {
+    let line = lines[index].1;
+    let indent = line.len() - line.trim_start().len();
+    let item = line.trim_start().starts_with("- ");
+
+    // Column of the entry's key; nested lines are indented further
+    let column = if item { indent + 2 } else { indent };
+
+    let end = lines[index + 1..]
+        .iter()
+        .position(|(_, l)| {
+            let trimmed = l.trim_start();
+            !trimmed.is_empty() && l.len() - trimmed.len() <= column
+        })
+        .map_or(lines.len(), |p| index + 1 + p);
+
+    if item && !line.trim_start()[2..].trim().is_empty() {
+        lines[index].1 = &line[..indent + 1];
+        lines.drain(index + 1..end);
+    } else {
+        lines.drain(index..end);
+    }
+}
+
+/// Remove the sequence item starting on line `index`, and everything nested below it.
+fn drop_item(lines: &mut Vec<(usize, &str)>, index: usize) {
+    let indent = indent_of(lines[index].1);
+
+    let end = lines[index + 1..]
+        .iter()
+        .position(|(_, l)| !l.trim().is_empty() && indent_of(l) <= indent)
+        .map_or(lines.len(), |p| index + 1 + p);
+
+    lines.drain(index..end);
+}
+
+/// Index of the line holding the entry which line `index` is nested below.
+fn parent(lines: &[(usize, &str)], index: usize) -> Option<usize> {
+    let indent = indent_of(lines[index].1);
+
+    lines[..index].iter().rposition(|(_, l)| {
+        let trimmed = l.trim_start();
+        // Keys of a sequence item are nested below the `- `
+        let column = if trimmed.starts_with("- ") {
+            indent_of(l) + 2
+        } else {
+            indent_of(l)
+        };
+
+        !trimmed.is_empty() && column < indent
+    })
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+///
+/// Quote scalar values which iRacing writes unquoted, but which are not valid YAML.
+///
+/// Free-text values such as user and team names are written as-is, so names containing
+/// `: ` or ` #`, starting with a YAML indicator (`*`, `&`, `,`, `[` ...), or starting with
+/// a quote they don't end with, would otherwise fail to parse.
+///
+/// Returns the input unchanged if no values needed quoting.
+pub fn sanitize_yaml(yaml: &str) -> Cow<'_, str> {
+    let mut output = String::new();
+    let mut changed = false;
+
+    for line in yaml.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[content.len()..];
+
+        match split_value(content) {
+            Some((key, value)) if needs_quoting(value) => {
+                output.push_str(key);
+                output.push('\'');
+                output.push_str(&value.replace('\'', "''"));
+                output.push('\'');
+                output.push_str(ending);
+                changed = true;
+            }
+            _ => output.push_str(line),
+        }
+    }
+
+    if changed {
+        Cow::Owned(output)
+    } else {
+        Cow::Borrowed(yaml)
+    }
+}
+
+/// Split a `Key: value` line (optionally a `- Key: value` sequence entry) into
+/// the part up to and including `": "`, and the value.
+fn split_value(line: &str) -> Option<(&str, &str)> {
+    let indent = line.len() - line.trim_start_matches([' ', '-']).len();
+    let rest = &line[indent..];
+
+    let key_length = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|&l| l > 0)?;
+
+    if !rest[key_length..].starts_with(": ") {
+        return None;
+    }
+
+    let split = indent + key_length + 2;
+    Some((&line[..split], &line[split..]))
+}
+
+fn needs_quoting(value: &str) -> bool {
+    let value = value.trim_end();
+    let first = match value.chars().next() {
+        Some(c) => c,
+        None => return false,
+    };
+
+    match first {
+        '"' => !is_double_quoted(value),
+        '\'' => !is_single_quoted(value),
+        '*' | '&' | '!' | '|' | '>' | '%' | '@' | '`' | ',' | '[' | ']' | '{' | '}' | '#' | '?'
+        | ':' => true,
+        _ => value.contains(": ") || value.contains(" #") || value.ends_with(':'),
+    }
+}
+
+fn is_double_quoted(value: &str) -> bool {
+    let inner = match value.get(1..).and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => inner,
+        None => return false,
+    };
+
+    // Every quote inside must be escaped
+    let mut escaped = false;
+
+    for c in inner.chars() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return false,
+            _ => escaped = false,
+        }
+    }
+
+    !escaped
+}
+
+fn is_single_quoted(value: &str) -> bool {
+    match value.get(1..).and_then(|v| v.strip_suffix('\'')) {
+        // Quotes inside must be doubled
+        Some(inner) => inner.replace("''", "").find('\'').is_none(),
+        None => false,
+    }
+}
+
+///
+/// Error converting a stringly-typed session field into a typed value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionValueError {
+    pub field: &'static str, // Name of the session field
+    pub value: String,       // The value which could not be understood
+}
+
+impl fmt::Display for SessionValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unexpected value for {}: \"{}\"", self.field, self.value)
+    }
+}
+
+impl Error for SessionValueError {}
+
+///
+/// Parse a `<number> <unit>` session value of the given dimension,
+/// returning it in the base unit of that dimension.
+fn base_value(
+    field: &'static str,
+    value: &str,
+    dimension: Dimension,
+) -> Result<f64, SessionValueError> {
+    match value.parse::<Quantity>() {
+        Ok(q) if q.dimension() == dimension => Ok(q.base()),
+        _ => Err(SessionValueError {
+            field,
+            value: value.to_owned(),
+        }),
+    }
+}
+
+///
+/// Parse the number at the start of a session value, ignoring any unit.
+fn leading_number(field: &'static str, value: &str) -> Result<f64, SessionValueError> {
+    value
+        .parse::<Quantity>()
+        .map(|q| q.value)
+        .map_err(|_| SessionValueError {
+            field,
+            value: value.to_owned(),
+        })
+}
+
+///
+/// Parse a 16-point compass direction (`N`, `NNE`, `NE` ... `NNW`) into radians from north.
+fn compass_direction(field: &'static str, value: &str) -> Result<f64, SessionValueError> {
+    const POINTS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW",
+        "NW", "NNW",
+    ];
+
+    POINTS
+        .iter()
+        .position(|p| p.eq_ignore_ascii_case(value.trim()))
+        .map(|i| i as f64 * std::f64::consts::PI / 8.0)
+        .ok_or_else(|| SessionValueError {
+            field,
+            value: value.to_owned(),
+        })
+}
+
+impl WeekendInfo {
+    /// Track length in metres
+    pub fn track_length_m(&self) -> Result<f64, SessionValueError> {
+        base_value("TrackLength", &self.track_length, Dimension::Length)
+    }
+
+    /// Track altitude in metres
+    pub fn track_altitude_m(&self) -> Result<f64, SessionValueError> {
+        base_value("TrackAltitude", &self.track_altitude, Dimension::Length)
+    }
+
+    /// Track latitude in degrees.
+    ///
+    /// iRacing reports the coordinates with a unit of "m", which is ignored.
+    pub fn track_latitude_deg(&self) -> Result<f64, SessionValueError> {
+        leading_number("TrackLatitude", &self.track_latitude)
+    }
+
+    /// Track longitude in degrees.
+    ///
+    /// iRacing reports the coordinates with a unit of "m", which is ignored.
+    pub fn track_longitude_deg(&self) -> Result<f64, SessionValueError> {
+        leading_number("TrackLongitude", &self.track_longitude)
+    }
+
+    /// Track rotation relative to true north, in radians
+    pub fn track_north_offset_rad(&self) -> Result<f64, SessionValueError> {
+        base_value(
+            "TrackNorthOffset",
+            &self.track_north_offset,
+            Dimension::Angle,
+        )
+    }
+
+    /// Pit speed limit in m/s
+    pub fn track_pit_speed_limit_ms(&self) -> Result<f64, SessionValueError> {
+        base_value(
+            "TrackPitSpeedLimit",
+            &self.track_pit_speed_limit,
+            Dimension::Speed,
+        )
+    }
+
+    /// Track surface temperature in °C
+    pub fn track_surface_temperature_c(&self) -> Result<f64, SessionValueError> {
+        base_value(
+            "TrackSurfaceTemp",
+            &self.track_surface_temperature,
+            Dimension::Temperature,
+        )
+    }
+
+    /// Air temperature in °C
+    pub fn track_air_temperature_c(&self) -> Result<f64, SessionValueError> {
+        base_value(
+            "TrackAirTemp",
+            &self.track_air_tempearture,
+            Dimension::Temperature,
+        )
+    }
+
+    /// Air pressure in Pa
+    pub fn track_air_pressure_pa(&self) -> Result<f64, SessionValueError> {
+        base_value(
+            "TrackAirPressure",
+            &self.track_air_pressure,
+            Dimension::Pressure,
+        )
+    }
+
+    /// Wind speed in m/s
+    pub fn track_wind_speed_ms(&self) -> Result<f64, SessionValueError> {
+        base_value("TrackWindVel", &self.track_wind_speed, Dimension::Speed)
+    }
+
+    /// Wind direction relative to north, in radians
+    pub fn track_wind_direction_rad(&self) -> Result<f64, SessionValueError> {
+        base_value("TrackWindDir", &self.track_wind_direction, Dimension::Angle)
+    }
+
+    /// Fog level as a fraction (0.0 - 1.0)
+    pub fn track_fog_level(&self) -> Result<f64, SessionValueError> {
+        base_value("TrackFogLevel", &self.track_fog_level, Dimension::Ratio)
+    }
+}
+
+impl WeekendOptions {
+    /// Wind direction relative to north, in radians
+    pub fn wind_direction_rad(&self) -> Result<f64, SessionValueError> {
+        compass_direction("WindDirection", &self.wind_direction)
+    }
+
+    /// Wind speed in m/s
+    pub fn wind_speed_ms(&self) -> Result<f64, SessionValueError> {
+        base_value("WindSpeed", &self.wind_speed, Dimension::Speed)
+    }
+
+    /// Temperature in °C
+    pub fn temperature_c(&self) -> Result<f64, SessionValueError> {
+        base_value("WeatherTemp", &self.temperature, Dimension::Temperature)
+    }
+
+    /// Relative humidity as a fraction (0.0 - 1.0)
+    pub fn relative_humidity(&self) -> Result<f64, SessionValueError> {
+        base_value(
+            "RelativeHumidity",
+            &self.relative_humidity,
+            Dimension::Ratio,
+        )
+    }
+
+    /// Fog level as a fraction (0.0 - 1.0)
+    pub fn fog_level(&self) -> Result<f64, SessionValueError> {
+        base_value("FogLevel", &self.fog_level, Dimension::Ratio)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION_YAML: &str = include_str!("../fixtures/session.yaml");
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn weekend_quantities() {
+        let session: SessionDetails = serde_yaml::from_str(SESSION_YAML).unwrap();
+        let weekend = &session.weekend;
+
+        assert!(close(weekend.track_length_m().unwrap(), 4860.0));
+        assert!(close(weekend.track_altitude_m().unwrap(), 41.67));
+        assert!(close(weekend.track_latitude_deg().unwrap(), 44.344224));
+        assert!(close(weekend.track_longitude_deg().unwrap(), 11.716519));
+        assert!(close(weekend.track_north_offset_rad().unwrap(), 4.9098));
+        assert!(close(weekend.track_pit_speed_limit_ms().unwrap(), 16.667));
+        assert!(close(weekend.track_surface_temperature_c().unwrap(), 40.56));
+        assert!((weekend.track_air_pressure_pa().unwrap() - 101_320.0).abs() < 1.0);
+        assert!(close(weekend.track_wind_speed_ms().unwrap(), 2.0));
+        assert!(close(weekend.track_fog_level().unwrap(), 0.0));
+
+        let options = &weekend.options;
+
+        assert!(close(options.wind_direction_rad().unwrap(), 0.0));
+        assert!(close(options.wind_speed_ms().unwrap(), 0.894));
+        assert!(close(options.temperature_c().unwrap(), 25.56));
+        assert!(close(options.relative_humidity().unwrap(), 0.55));
+    }
+
+    #[test]
+    fn session_limits() {
+        let session: SessionDetails = serde_yaml::from_str(SESSION_YAML).unwrap();
+        let sessions = &session.session.sessions;
+
+        assert_eq!(sessions[0].limit().unwrap(), SessionLimit::Time(600.0));
+        assert_eq!(
+            sessions[1].limit().unwrap(),
+            SessionLimit::LapsOrTime {
+                laps: 3,
+                time: 3000.0
+            }
+        );
+        assert_eq!(sessions[2].limit().unwrap(), SessionLimit::Time(10800.0));
+
+        assert!(sessions[0]
+            .limit()
+            .unwrap()
+            .is_consistent_with(32767, 412.5));
+        assert!(!sessions[0].limit().unwrap().is_consistent_with(5, 412.5));
+        assert!(!sessions[0]
+            .limit()
+            .unwrap()
+            .is_consistent_with(32767, 900.0));
+        assert_eq!(
+            SessionLimit::from_remaining(UNLIMITED_LAPS, UNLIMITED_TIME as f64),
+            SessionLimit::Unlimited
+        );
+        assert_eq!(
+            SessionLimit::from_remaining(UNLIMITED_LAPS, -5.593),
+            SessionLimit::Time(0.0)
+        );
+    }
+
+    #[test]
+    fn predict_session_end() {
+        // 10 minutes left, leader 20.5 laps in, lapping in 100s: 6 more laps to lap 27.
+        let timed = SessionLimit::Time(3600.0);
+        let end = timed.predict_end(20.5, 600.0, 100.0).unwrap();
+        assert_eq!(end.ends_on, SessionEnd::Time);
+        assert_eq!(end.total_laps, 27);
+        assert!((end.laps_to_go - 6.5).abs() < 1e-9);
+
+        // A 25 lap limit is reached before the clock runs out.
+        let either = SessionLimit::LapsOrTime {
+            laps: 25,
+            time: 3600.0,
+        };
+        let end = either.predict_end(20.5, 600.0, 100.0).unwrap();
+        assert_eq!(end.ends_on, SessionEnd::Laps);
+        assert_eq!(end.total_laps, 25);
+        assert!((end.time_to_go - 450.0).abs() < 1e-9);
+
+        assert!(SessionLimit::Unlimited
+            .predict_end(20.5, 600.0, 100.0)
+            .is_none());
+    }
+
+    #[test]
+    fn session_sections() {
+        let session: SessionDetails = serde_yaml::from_str(SESSION_YAML).unwrap();
+
+        let race = &session.session.sessions[2];
+        assert_eq!(race.session_name.as_deref(), Some("RACE"));
+        assert_eq!(race.session_sub_type, None);
+        assert_eq!(race.fastest_laps.as_ref().unwrap()[0].car_idx, 3);
+        assert_eq!(race.average_lap_time, Some(105.4479));
+        assert_eq!(race.caution_flags, Some(0));
+        assert_eq!(race.laps_complete, Some(103));
+
+        let qualifying = session.qualify_results.unwrap();
+        assert_eq!(qualifying.results.len(), 3);
+        assert_eq!(qualifying.results[0].car_idx, 3);
+
+        let cameras = session.cameras.unwrap();
+        assert_eq!(cameras.groups.len(), 4);
+        assert_eq!(cameras.groups[2].cameras[1].name, "CamTV1_01");
+        assert!(cameras.groups[3].is_scenic);
+        assert!(!cameras.groups[0].is_scenic);
+
+        let radios = session.radios.unwrap();
+        assert_eq!(radios.radios[0].frequencies[2].name, "@TEAM");
+        assert_eq!(radios.radios[0].frequencies[2].car_idx, 2);
+
+        let sectors = session.split_times.unwrap().sectors;
+        assert_eq!(sectors.len(), 3);
+        assert!((sectors[1].start_percent - 0.321843).abs() < 1e-6);
+
+        let setup = session.car_setup.unwrap();
+        assert_eq!(setup.update_count, 3);
+        assert!(setup.extra.contains_key("TiresAero"));
+        assert!(setup.extra.contains_key("Chassis"));
+    }
+
+    #[test]
+    fn sanitize_bad_names() {
+        let bad = include_str!("../fixtures/session_bad_names.yaml");
+
+        assert!(serde_yaml::from_str::<SessionDetails>(bad).is_err());
+
+        let session = SessionDetails::from_yaml(bad).unwrap();
+        let drivers = &session.drivers.other_drivers;
+
+        assert_eq!(drivers[1].team_name, "Backmarker Racing: Razzle Dazzle");
+        assert_eq!(drivers[1].abbrev_name.as_deref(), Some(","));
+        assert_eq!(drivers[2].user_name, "*Freddie Skitini");
+        assert_eq!(drivers[2].team_name, "Racing Prong #23");
+        assert_eq!(drivers[3].user_name, "\"Flying\" Phil Baldwin");
+        assert_eq!(drivers[3].team_name, "DHR [Jolly] Green Giant:");
+    }
+
+    #[test]
+    fn sanitize_leaves_valid_yaml() {
+        assert!(matches!(sanitize_yaml(SESSION_YAML), Cow::Borrowed(_)));
+
+        assert_eq!(
+            sanitize_yaml(" - UserName: it's: me\r\n   CarNumber: \"12\"\r\n"),
+            " - UserName: 'it''s: me'\r\n   CarNumber: \"12\"\r\n"
+        );
+        assert_eq!(
+            sanitize_yaml("Abbrev: 'Quote''s'\n"),
+            "Abbrev: 'Quote''s'\n"
+        );
+        assert_eq!(sanitize_yaml("Name: \"A\" B\n"), "Name: '\"A\" B'\n");
+    }
+
+    #[test]
+    fn unknown_keys_kept() {
+        let session = SessionDetails::from_yaml(SESSION_YAML).unwrap();
+
+        assert_eq!(
+            session.weekend.extra.get("TrackVersion"),
+            Some(&serde_yaml::Value::from("2020.11.23.01"))
+        );
+        assert!(session.weekend.extra.contains_key("TelemetryOptions"));
+        assert!(session.weekend.options.extra.contains_key("TimeOfDay"));
+        assert!(session.session.sessions[0].results.as_ref().unwrap()[0]
+            .extra
+            .is_empty());
+        assert!(session.drivers.extra.contains_key("DriverUserID"));
+
+        let driver = &session.drivers.other_drivers[1];
+        assert!(driver.extra.contains_key("HelmetDesignStr"));
+        assert!(!driver.extra.contains_key("CarDesignStr"));
+        assert!(!session.weekend.extra.contains_key("TrackName"));
+
+        let yaml = SESSION_YAML.replace(
+            "     CameraName: CamTV1_01\n",
+            "     CameraName: CamTV1_01\n     CameraFov: 0x2a\n",
+        );
+        let session: SessionDetails = serde_yaml::from_str(&yaml).unwrap();
+        let camera = &session.cameras.unwrap().groups[2].cameras[1];
+        assert_eq!(camera.name, "CamTV1_01");
+        assert_eq!(camera.extra.len(), 1);
+        assert!(camera.extra.contains_key("CameraFov"));
+    }
+
+    #[test]
+    fn missing_fields() {
+        let session = SessionDetails::from_yaml(SESSION_YAML).unwrap();
+        assert_eq!(session.weekend.track_config_name, None);
+        assert_eq!(session.drivers.other_drivers[0].abbrev_name, None);
+        assert_eq!(session.drivers.other_drivers[0].car_class_color, "0x000000");
+
+        let yaml = SESSION_YAML
+            .lines()
+            .filter(|l| !l.contains("ClubName") && !l.contains("TrackCleanup"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let session = SessionDetails::from_yaml(&yaml).unwrap();
+        assert_eq!(session.weekend.track_cleanup, None);
+        assert_eq!(session.drivers.other_drivers[1].club_name, None);
+
+        let yaml = SESSION_YAML.replace(" TrackID: 266\n", "");
+        let err = SessionDetails::from_yaml(&yaml).unwrap_err();
+        assert!(
+            err.to_string().contains("missing field `TrackID`"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn lenient_parse() {
+        let yaml = SESSION_YAML
+            .replace(" TrackCleanup: 0", " TrackCleanup: none")
+            .replace("   UserID: 315629", "   UserID: [315629]")
+            .replace(" - SessionNum: 1\n", " - SessionNum: one\n");
+
+        assert!(SessionDetails::from_yaml(&yaml).is_err());
+
+        let drivers = SessionDetails::from_yaml(SESSION_YAML)
+            .unwrap()
+            .drivers
+            .other_drivers
+            .len();
+        let (session, warnings) = SessionDetails::from_yaml_lenient(&yaml).unwrap();
+
+        assert_eq!(warnings.len(), 5, "{:#?}", warnings);
+        assert_eq!(warnings[0].line, 28);
+        assert_eq!(session.weekend.track_cleanup, None);
+        assert_eq!(session.weekend.track_name, "imola gp");
+
+        // Entries with an invalid required field are dropped
+        assert_eq!(warnings[1].line, 137);
+        assert!(warnings[2].message.contains("missing field `SessionNum`"));
+        assert_eq!(session.session.sessions.len(), 2);
+        assert_eq!(session.session.sessions[1].session_number, 2);
+
+        assert!(warnings[4].message.contains("missing field `UserID`"));
+        assert_eq!(session.drivers.other_drivers.len(), drivers - 1);
+        assert_eq!(session.drivers.other_drivers[2].user_id, 332748);
+    }
+
+    #[test]
+    fn unexpected_weekend_values() {
+        let mut session: SessionDetails = serde_yaml::from_str(SESSION_YAML).unwrap();
+
+        session.weekend.track_length = "4.86 kph".to_owned();
+        session.weekend.options.wind_direction = "Up".to_owned();
+
+        let err = session.weekend.track_length_m().unwrap_err();
+        assert_eq!(err.field, "TrackLength");
+        assert_eq!(err.value, "4.86 kph");
+
+        assert!(session.weekend.options.wind_direction_rad().is_err());
+    }
 }
@@ -0,0 +1,381 @@
+//!
+//! Changes between two versions of the session info.
+//!
+//! iRacing increments `session_info_version` each time the session info is rewritten.
+//! `SessionDetails::diff` compares two versions and reports drivers joining and leaving,
+//! team driver changes, result updates, weather changes and new sessions as `SessionChange`s.
+
+use crate::session::{Driver, Session, SessionDetails, SessionResult, WeekendInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+///
+/// A change to the session info
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SessionChange {
+    DriverJoined {
+        car_idx: usize,
+        user_id: i64,
+        user_name: String,
+        team_name: String,
+    },
+    DriverLeft {
+        car_idx: usize,
+        user_id: i64,
+        user_name: String,
+    },
+    /// A different driver of the same team took over the car
+    TeamDriverChanged {
+        car_idx: usize,
+        team_id: u64,
+        team_name: String,
+        from_user_id: i64,
+        from_user_name: String,
+        to_user_id: i64,
+        to_user_name: String,
+    },
+    ResultAdded {
+        session_number: u64,
+        result: SessionResult,
+    },
+    ResultRemoved {
+        session_number: u64,
+        result: SessionResult,
+    },
+    ResultChanged {
+        session_number: u64,
+        from: SessionResult,
+        to: SessionResult,
+    },
+    WeatherChanged {
+        field: String, // Session info key, e.g. `TrackAirTemp`
+        from: String,
+        to: String,
+    },
+    SessionAdded {
+        session_number: u64,
+        session_type: String,
+    },
+    SessionRemoved {
+        session_number: u64,
+        session_type: String,
+    },
+}
+
+/// Weather values in `WeekendInfo`, by session info key
+fn weather(weekend: &WeekendInfo) -> BTreeMap<&'static str, String> {
+    let mut values = BTreeMap::new();
+
+    values.insert("TrackWeatherType", weekend.track_weather.clone());
+    values.insert("TrackSkies", weekend.track_skies.clone());
+    values.insert(
+        "TrackSurfaceTemp",
+        weekend.track_surface_temperature.clone(),
+    );
+    values.insert("TrackAirTemp", weekend.track_air_tempearture.clone());
+    values.insert("TrackAirPressure", weekend.track_air_pressure.clone());
+    values.insert("TrackWindVel", weekend.track_wind_speed.clone());
+    values.insert("TrackWindDir", weekend.track_wind_direction.clone());
+    values.insert("TrackFogLevel", weekend.track_fog_level.clone());
+
+    // Not modelled by `WeekendInfo`, but present in newer builds
+    for key in &["TrackRelativeHumidity", "TrackPrecipitation"] {
+        if let Some(value) = weekend.extra.get(*key) {
+            let value = match value {
+                serde_yaml::Value::String(s) => s.clone(),
+                other => serde_yaml::to_string(other)
+                    .unwrap_or_default()
+                    .trim_start_matches("---")
+                    .trim()
+                    .to_owned(),
+            };
+
+            values.insert(key, value);
+        }
+    }
+
+    values
+}
+
+fn diff_drivers(before: &[Driver], after: &[Driver], changes: &mut Vec<SessionChange>) {
+    let before: BTreeMap<usize, &Driver> = before.iter().map(|d| (d.index, d)).collect();
+    let after: BTreeMap<usize, &Driver> = after.iter().map(|d| (d.index, d)).collect();
+
+    let left = |d: &Driver| SessionChange::DriverLeft {
+        car_idx: d.index,
+        user_id: d.user_id,
+        user_name: d.user_name.clone(),
+    };
+
+    let joined = |d: &Driver| SessionChange::DriverJoined {
+        car_idx: d.index,
+        user_id: d.user_id,
+        user_name: d.user_name.clone(),
+        team_name: d.team_name.clone(),
+    };
+
+    for (idx, old) in &before {
+        match after.get(idx) {
+            None => changes.push(left(old)),
+            Some(new) if new.user_id == old.user_id => {}
+            Some(new) if new.team_id != 0 && new.team_id == old.team_id => {
+                changes.push(SessionChange::TeamDriverChanged {
+                    car_idx: *idx,
+                    team_id: new.team_id,
+                    team_name: new.team_name.clone(),
+                    from_user_id: old.user_id,
+                    from_user_name: old.user_name.clone(),
+                    to_user_id: new.user_id,
+                    to_user_name: new.user_name.clone(),
+                })
+            }
+            Some(new) => {
+                changes.push(left(old));
+                changes.push(joined(new));
+            }
+        }
+    }
+
+    for (idx, new) in &after {
+        if !before.contains_key(idx) {
+            changes.push(joined(new));
+        }
+    }
+}
+
+fn diff_results(before: &Session, after: &Session, changes: &mut Vec<SessionChange>) {
+    let by_car = |s: &Session| -> BTreeMap<i32, SessionResult> {
+        s.results
+            .iter()
+            .flatten()
+            .map(|r| (r.car_idx, r.clone()))
+            .collect()
+    };
+
+    let session_number = after.session_number;
+    let old_results = by_car(before);
+    let new_results = by_car(after);
+
+    for (car_idx, old) in &old_results {
+        match new_results.get(car_idx) {
+            None => changes.push(SessionChange::ResultRemoved {
+                session_number,
+                result: old.clone(),
+            }),
+            Some(new) if new != old => changes.push(SessionChange::ResultChanged {
+                session_number,
+                from: old.clone(),
+                to: new.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for (car_idx, new) in &new_results {
+        if !old_results.contains_key(car_idx) {
+            changes.push(SessionChange::ResultAdded {
+                session_number,
+                result: new.clone(),
+            });
+        }
+    }
+}
+
+impl SessionDetails {
+    ///
+    /// Changes from this session info to a later version of it.
+    ///
+    /// Changes are reported in the order: drivers, weather, sessions, then results of each session.
+    pub fn diff(&self, later: &SessionDetails) -> Vec<SessionChange> {
+        let mut changes = vec![];
+
+        diff_drivers(
+            &self.drivers.other_drivers,
+            &later.drivers.other_drivers,
+            &mut changes,
+        );
+
+        let old_weather = weather(&self.weekend);
+        let new_weather = weather(&later.weekend);
+
+        for (field, to) in &new_weather {
+            match old_weather.get(field) {
+                Some(from) if from != to => changes.push(SessionChange::WeatherChanged {
+                    field: field.to_string(),
+                    from: from.clone(),
+                    to: to.clone(),
+                }),
+                _ => {}
+            }
+        }
+
+        let old_sessions: BTreeMap<u64, &Session> = self
+            .session
+            .sessions
+            .iter()
+            .map(|s| (s.session_number, s))
+            .collect();
+        let new_sessions: BTreeMap<u64, &Session> = later
+            .session
+            .sessions
+            .iter()
+            .map(|s| (s.session_number, s))
+            .collect();
+
+        for (number, session) in &old_sessions {
+            if !new_sessions.contains_key(number) {
+                changes.push(SessionChange::SessionRemoved {
+                    session_number: *number,
+                    session_type: session.session_type.clone(),
+                });
+            }
+        }
+
+        for (number, session) in &new_sessions {
+            if !old_sessions.contains_key(number) {
+                changes.push(SessionChange::SessionAdded {
+                    session_number: *number,
+                    session_type: session.session_type.clone(),
+                });
+            }
+        }
+
+        for (number, new) in &new_sessions {
+            if let Some(old) = old_sessions.get(number) {
+                diff_results(old, new, &mut changes);
+            } else {
+                diff_results(&Session::default(), new, &mut changes);
+            }
+        }
+
+        changes
+    }
+}
+
+///
+/// Tracks session info across updates, reporting what changed each time
+/// the session info version increments.
+#[derive(Debug, Clone, Default)]
+pub struct SessionWatcher {
+    last: Option<(i32, SessionDetails)>,
+}
+
+impl SessionWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Record the session info for a `session_info_version`.
+    ///
+    /// Returns the changes since the previous version, or `None` for the first
+    /// session info seen and for repeats of the same version.
+    pub fn update(&mut self, version: i32, details: SessionDetails) -> Option<Vec<SessionChange>> {
+        let changes = match &self.last {
+            Some((last, _)) if *last == version => return None,
+            Some((_, previous)) => Some(previous.diff(&details)),
+            None => None,
+        };
+
+        self.last = Some((version, details));
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION_YAML: &str = include_str!("../fixtures/session.yaml");
+
+    #[test]
+    fn session_changes() {
+        let before = SessionDetails::from_yaml(SESSION_YAML).unwrap();
+        let mut after = before.clone();
+
+        // Car 2 is taken over by a team mate, car 3 leaves and a new car joins
+        let drivers = &mut after.drivers.other_drivers;
+        drivers[2].user_id = 400001;
+        drivers[2].user_name = "Jo Skitini".to_owned();
+        drivers.remove(3);
+
+        let mut newcomer = drivers[1].clone();
+        newcomer.index = 4;
+        newcomer.user_id = 400002;
+        newcomer.user_name = "Sam Late".to_owned();
+        drivers.push(newcomer);
+
+        after.weekend.track_air_tempearture = "26.11 C".to_owned();
+        after.weekend.extra.insert(
+            "TrackRelativeHumidity".to_owned(),
+            serde_yaml::Value::from("60 %"),
+        );
+
+        let race = after.session.sessions[2].results.as_mut().unwrap();
+        race[1].position = 3;
+        race.remove(2);
+
+        let mut heat = after.session.sessions[1].clone();
+        heat.session_number = 3;
+        heat.session_type = "Heat Race".to_owned();
+        after.session.sessions.push(heat);
+
+        let changes = before.diff(&after);
+        let types: Vec<_> = changes
+            .iter()
+            .map(|c| serde_json::to_value(c).unwrap()["type"].clone())
+            .collect();
+
+        assert_eq!(
+            types,
+            vec![
+                "TeamDriverChanged",
+                "DriverLeft",
+                "DriverJoined",
+                "WeatherChanged",
+                "WeatherChanged",
+                "SessionAdded",
+                "ResultChanged",
+                "ResultRemoved",
+                "ResultAdded",
+            ]
+        );
+
+        assert_eq!(
+            changes[0],
+            SessionChange::TeamDriverChanged {
+                car_idx: 2,
+                team_id: 158499,
+                team_name: "Racing Prong".to_owned(),
+                from_user_id: 315629,
+                from_user_name: "Freddie Skitini".to_owned(),
+                to_user_id: 400001,
+                to_user_name: "Jo Skitini".to_owned(),
+            }
+        );
+        assert_eq!(
+            changes[3],
+            SessionChange::WeatherChanged {
+                field: "TrackAirTemp".to_owned(),
+                from: "25.56 C".to_owned(),
+                to: "26.11 C".to_owned(),
+            }
+        );
+        assert!(matches!(
+            &changes[6],
+            SessionChange::ResultChanged { session_number: 2, from, to }
+                if from.position == 2 && to.position == 3
+        ));
+    }
+
+    #[test]
+    fn watcher() {
+        let details = SessionDetails::from_yaml(SESSION_YAML).unwrap();
+        let mut watcher = SessionWatcher::new();
+
+        assert!(watcher.update(1, details.clone()).is_none());
+        assert!(watcher.update(1, details.clone()).is_none());
+        assert_eq!(watcher.update(2, details), Some(vec![]));
+    }
+}
@@ -1,29 +1,680 @@
-use std::path::Path;
-use std::fs::File;
-use std::io::prelude::*;
-use std::convert::TryInto;
+//!
+//! Car setups.
+//!
+//! `SetupTree` is a generic, typed view of the car-specific `CarSetup` section of the session
+//! info, which can be searched by path and compared between setup changes. `SetupLibrary` indexes
+//! the `.sto` files saved by iRacing; their format is undocumented, so their contents are not decoded.
+
+use crate::quantity::{Quantity, Unit};
+use crate::session::{CarSetup, SessionDetails};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value as YamlValue;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
 use std::io;
+use std::path::{Path, PathBuf};
+
+///
+/// A single value of a car setup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SetupValue {
+    Quantity(Quantity), // A number with a unit, e.g. "26.5 psi", "-2.8 deg" or "55.2%"
+    Clicks(i32),        // Adjuster clicks, e.g. "8 clicks"
+    Number(f64),        // A plain number
+    Text(String),       // Anything else, e.g. "Race1" or "4 (TC)"
+}
+
+impl From<&str> for SetupValue {
+    fn from(s: &str) -> Self {
+        let trimmed = s.trim();
+
+        let quantity = match trimmed.parse::<Quantity>() {
+            Ok(q) => q,
+            Err(_) => return SetupValue::Text(trimmed.to_owned()),
+        };
+
+        match quantity.unit {
+            Unit::None => SetupValue::Number(quantity.value),
+            Unit::Other(ref u) if u == "clicks" || u == "click" => {
+                SetupValue::Clicks(quantity.value as i32)
+            }
+            // Only accept a single word as a unit, so values like "4 (TC)" or
+            // "82C, 85C, 88C" are kept as text
+            Unit::Other(ref u) if u.contains(|c: char| c.is_whitespace() || ",()".contains(c)) => {
+                SetupValue::Text(trimmed.to_owned())
+            }
+            _ => SetupValue::Quantity(quantity),
+        }
+    }
+}
+
+impl From<&YamlValue> for SetupValue {
+    fn from(v: &YamlValue) -> Self {
+        match v {
+            YamlValue::String(s) => SetupValue::from(s.as_str()),
+            YamlValue::Number(n) => SetupValue::Number(n.as_f64().unwrap_or_default()),
+            YamlValue::Bool(b) => SetupValue::Text(b.to_string()),
+            YamlValue::Null => SetupValue::Text(String::new()),
+            other => SetupValue::Text(
+                serde_yaml::to_string(other)
+                    .unwrap_or_default()
+                    .trim_start_matches("---")
+                    .trim()
+                    .to_owned(),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for SetupValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupValue::Quantity(q) => write!(f, "{}", q),
+            SetupValue::Clicks(c) => write!(f, "{} clicks", c),
+            SetupValue::Number(n) => write!(f, "{}", n),
+            SetupValue::Text(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+///
+/// Either a section of the setup, or a value within a section.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SetupNode {
+    Section(SetupTree),
+    Value(SetupValue),
+}
+
+///
+/// Generic car setup, as a tree of named sections with typed values as leaves.
+///
+/// Values are found by their dot-separated path, e.g. `Chassis.LeftFront.Camber`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SetupTree {
+    pub children: BTreeMap<String, SetupNode>,
+}
+
+///
+/// A difference between two setups
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SetupChange {
+    Added {
+        path: String,
+        value: SetupValue,
+    },
+    Removed {
+        path: String,
+        value: SetupValue,
+    },
+    Changed {
+        path: String,
+        from: SetupValue,
+        to: SetupValue,
+    },
+}
+
+impl SetupChange {
+    pub fn path(&self) -> &str {
+        match self {
+            SetupChange::Added { path, .. }
+            | SetupChange::Removed { path, .. }
+            | SetupChange::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for SetupChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupChange::Added { path, value } => write!(f, "+ {}: {}", path, value),
+            SetupChange::Removed { path, value } => write!(f, "- {}: {}", path, value),
+            SetupChange::Changed { path, from, to } => write!(f, "~ {}: {} -> {}", path, from, to),
+        }
+    }
+}
+
+impl SetupTree {
+    ///
+    /// Build a tree from a YAML mapping.
+    ///
+    /// Returns `None` if the value is not a mapping.
+    pub fn from_yaml(yaml: &YamlValue) -> Option<Self> {
+        let mapping = yaml.as_mapping()?;
+        let mut tree = SetupTree::default();
+
+        for (key, value) in mapping {
+            let name = match key {
+                YamlValue::String(s) => s.clone(),
+                other => SetupValue::from(other).to_string(),
+            };
+
+            let node = match SetupTree::from_yaml(value) {
+                Some(section) => SetupNode::Section(section),
+                None => SetupNode::Value(SetupValue::from(value)),
+            };
+
+            tree.children.insert(name, node);
+        }
+
+        Some(tree)
+    }
+
+    ///
+    /// Find the section or value at a dot-separated path
+    pub fn get(&self, path: &str) -> Option<&SetupNode> {
+        let mut parts = path.split('.');
+        let mut node = self.children.get(parts.next()?)?;
+
+        for part in parts {
+            node = match node {
+                SetupNode::Section(tree) => tree.children.get(part)?,
+                SetupNode::Value(_) => return None,
+            };
+        }
+
+        Some(node)
+    }
 
-pub struct Setup;
+    ///
+    /// Find the value at a dot-separated path
+    pub fn value(&self, path: &str) -> Option<&SetupValue> {
+        match self.get(path)? {
+            SetupNode::Value(v) => Some(v),
+            SetupNode::Section(_) => None,
+        }
+    }
+
+    ///
+    /// All values in the tree, keyed by their path
+    pub fn values(&self) -> BTreeMap<String, &SetupValue> {
+        let mut values = BTreeMap::new();
+        self.collect_values("", &mut values);
+        values
+    }
+
+    fn collect_values<'a>(&'a self, prefix: &str, values: &mut BTreeMap<String, &'a SetupValue>) {
+        for (name, node) in &self.children {
+            let path = if prefix.is_empty() {
+                name.clone()
+            } else {
+                format!("{}.{}", prefix, name)
+            };
+
+            match node {
+                SetupNode::Section(tree) => tree.collect_values(&path, values),
+                SetupNode::Value(v) => {
+                    values.insert(path, v);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Values which differ between this setup and `other`, in path order.
+    pub fn diff(&self, other: &SetupTree) -> Vec<SetupChange> {
+        let before = self.values();
+        let after = other.values();
+        let mut changes = vec![];
 
-impl Setup {
+        for (path, from) in &before {
+            match after.get(path) {
+                Some(to) if to != from => changes.push(SetupChange::Changed {
+                    path: path.clone(),
+                    from: (*from).clone(),
+                    to: (*to).clone(),
+                }),
+                Some(_) => {}
+                None => changes.push(SetupChange::Removed {
+                    path: path.clone(),
+                    value: (*from).clone(),
+                }),
+            }
+        }
 
-    pub fn new(data: Vec<u8>) -> io::Result<Self> {
+        for (path, value) in &after {
+            if !before.contains_key(path) {
+                changes.push(SetupChange::Added {
+                    path: path.clone(),
+                    value: (*value).clone(),
+                });
+            }
+        }
 
-        let ints: Vec<i32> = data.chunks_exact(4).map( |bytes| {
-            i32::from_le_bytes(bytes.try_into().unwrap())
-        }).collect();
+        changes.sort_by(|a, b| a.path().cmp(b.path()));
+        changes
+    }
+}
+
+impl CarSetup {
+    ///
+    /// The setup as a `SetupTree`
+    pub fn tree(&self) -> SetupTree {
+        let mut tree = SetupTree::default();
 
-        println!("Ints: {:#?}", ints);
+        for (name, section) in &self.extra {
+            let node = match SetupTree::from_yaml(section) {
+                Some(section) => SetupNode::Section(section),
+                None => SetupNode::Value(SetupValue::from(section)),
+            };
 
-        Ok(Self{})
+            tree.children.insert(name.clone(), node);
+        }
+
+        tree
     }
+}
+
+///
+/// Tracks the player's setup across session info updates, reporting what
+/// changed each time the setup's `UpdateCount` increments.
+#[derive(Debug, Clone, Default)]
+pub struct SetupWatcher {
+    last: Option<(u32, SetupTree)>,
+}
+
+impl SetupWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Record the latest setup.
+    ///
+    /// Returns the changes since the previous setup if the update count has changed,
+    /// or `None` for the first setup seen and for repeats of the same update.
+    pub fn update(&mut self, setup: &CarSetup) -> Option<Vec<SetupChange>> {
+        let changes = match &self.last {
+            Some((count, _)) if *count == setup.update_count => return None,
+            Some((_, previous)) => Some(previous.diff(&setup.tree())),
+            None => None,
+        };
+
+        self.last = Some((setup.update_count, setup.tree()));
+        changes
+    }
+}
+
+///
+/// Content hash of a setup file (64-bit FNV-1a), used to find duplicate setups.
+pub fn content_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+///
+/// A setup file found by `SetupLibrary`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupEntry {
+    pub path: PathBuf,
+    pub car: String,            // Car folder, matching `Driver.car_path`
+    pub track: Option<String>,  // Track folder, if any
+    pub season: Option<String>, // Season folder (e.g. `2021S2`), if any
+    pub file_name: String,
+    pub hash: u64, // `content_hash` of the file
+    pub size: u64, // Size of the file, in bytes
+}
+
+///
+/// Index of the `.sto` files in an iRacing `setups` folder.
+///
+/// Files are expected to be laid out as `<root>/<car>/[<track>/][<season>/]<name>.sto`.
+/// Folders which look like a season (`2021S2`, `21 S2`, `Season 2 2021`) are taken as the season,
+/// the first other folder below the car as the track. The track and season folders may be in either order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SetupLibrary {
+    pub entries: Vec<SetupEntry>,
+    pub errors: Vec<(PathBuf, String)>, // Files which could not be read
+}
+
+/// Test if a folder name looks like a season, e.g. `2021S2`, `21 S2`, `S2 2021` or `Season 2`
+fn is_season_folder(name: &str) -> bool {
+    let lower = name.to_lowercase();
+
+    if lower.contains("season") {
+        return true;
+    }
+
+    let compact: String = lower
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+        .collect();
+
+    let is_year = |s: &str| (s.len() == 2 || s.len() == 4) && s.chars().all(|c| c.is_ascii_digit());
+    let is_quarter = |s: &str| s.len() == 1 && s.chars().all(|c| c.is_ascii_digit());
+
+    match compact.find('s') {
+        Some(0) => compact[1..]
+            .char_indices()
+            .any(|(i, _)| is_quarter(&compact[1..i + 1]) && is_year(&compact[i + 1..])),
+        Some(i) => is_year(&compact[..i]) && is_quarter(&compact[i + 1..]),
+        None => false,
+    }
+}
+
+impl SetupLibrary {
+    ///
+    /// Scan `root` (usually `Documents/iRacing/setups`) and index every `.sto` file found.
+    ///
+    /// Files which can't be read are recorded in `errors`. Only failures to read the directory tree
+    /// itself are returned as errors.
+    pub fn scan(root: &Path) -> io::Result<Self> {
+        let mut library = SetupLibrary::default();
+        let mut pending = vec![root.to_path_buf()];
+
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+
+                if path.is_dir() {
+                    pending.push(path);
+                } else if path
+                    .extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("sto"))
+                {
+                    library.add(root, path);
+                }
+            }
+        }
+
+        library.entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(library)
+    }
+
+    fn add(&mut self, root: &Path, path: PathBuf) {
+        let folders: Vec<String> = path
+            .parent()
+            .and_then(|p| p.strip_prefix(root).ok())
+            .map(|p| {
+                p.components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Files directly inside the root don't belong to a car
+        let (car, rest) = match folders.split_first() {
+            Some((car, rest)) => (car.clone(), rest),
+            None => return,
+        };
+
+        let season = rest.iter().find(|f| is_season_folder(f)).cloned();
+        let track = rest.iter().find(|f| !is_season_folder(f)).cloned();
+
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) => {
+                self.errors.push((path, e.to_string()));
+                return;
+            }
+        };
+
+        self.entries.push(SetupEntry {
+            file_name: path
+                .file_name()
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path,
+            car,
+            track,
+            season,
+            hash: content_hash(&data),
+            size: data.len() as u64,
+        });
+    }
+
+    /// Setups for a car, by its `car_path`
+    pub fn for_car<'a>(&'a self, car: &'a str) -> impl Iterator<Item = &'a SetupEntry> + 'a {
+        self.entries
+            .iter()
+            .filter(move |e| e.car.eq_ignore_ascii_case(car))
+    }
+
+    /// Setups for a car at a track
+    pub fn for_track<'a>(
+        &'a self,
+        car: &'a str,
+        track: &'a str,
+    ) -> impl Iterator<Item = &'a SetupEntry> + 'a {
+        self.for_car(car).filter(move |e| {
+            e.track
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case(track))
+        })
+    }
+
+    /// Setups with the given content hash
+    pub fn by_hash(&self, hash: u64) -> impl Iterator<Item = &SetupEntry> {
+        self.entries.iter().filter(move |e| e.hash == hash)
+    }
+
+    ///
+    /// Groups of files with identical contents
+    pub fn duplicates(&self) -> Vec<Vec<&SetupEntry>> {
+        let mut by_hash: BTreeMap<u64, Vec<&SetupEntry>> = BTreeMap::new();
+
+        for entry in &self.entries {
+            by_hash.entry(entry.hash).or_default().push(entry);
+        }
+
+        by_hash
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .collect()
+    }
+
+    ///
+    /// One entry for each distinct file content
+    pub fn unique(&self) -> Vec<&SetupEntry> {
+        let mut seen = HashSet::new();
+
+        self.entries
+            .iter()
+            .filter(|e| seen.insert(e.hash))
+            .collect()
+    }
+
+    ///
+    /// Find the setup loaded in the given session (`DriverInfo.setup_name`) for the player's car.
+    ///
+    /// When several files share the name, a file in a folder matching the session's track is preferred.
+    pub fn find_session_setup(&self, session: &SessionDetails) -> Option<&SetupEntry> {
+        let drivers = &session.drivers;
+        let car = &drivers
+            .other_drivers
+            .iter()
+            .find(|d| d.index == drivers.car_index)?
+            .car_path;
+
+        let name = drivers.setup_name.as_str();
+        let track = session.weekend.track_name.to_lowercase();
+
+        let mut candidates = self
+            .entries
+            .iter()
+            .filter(|e| e.car.eq_ignore_ascii_case(car) && e.file_name.eq_ignore_ascii_case(name));
+
+        let first = candidates.next()?;
+        let track_matches = |e: &&SetupEntry| {
+            e.track.as_ref().is_some_and(|t| {
+                let t = t.to_lowercase();
+                track.starts_with(&t) || t.starts_with(&track)
+            })
+        };
+
+        if track_matches(&first) {
+            return Some(first);
+        }
+
+        Some(candidates.find(track_matches).unwrap_or(first))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::SessionDetails;
+
+    const SESSION_YAML: &str = include_str!("../fixtures/session.yaml");
+
+    fn setup() -> CarSetup {
+        let session: SessionDetails = serde_yaml::from_str(SESSION_YAML).unwrap();
+        session.car_setup.unwrap()
+    }
+
+    #[test]
+    fn setup_values() {
+        assert_eq!(
+            SetupValue::from("26.5 psi"),
+            SetupValue::Quantity(Quantity::new(26.5, Unit::Psi))
+        );
+        assert_eq!(
+            SetupValue::from("-2.8 deg"),
+            SetupValue::Quantity(Quantity::new(-2.8, Unit::Degrees))
+        );
+        assert_eq!(
+            SetupValue::from("55.2%"),
+            SetupValue::Quantity(Quantity::new(55.2, Unit::Percent))
+        );
+        assert_eq!(SetupValue::from("8 clicks"), SetupValue::Clicks(8));
+        assert_eq!(SetupValue::from("3"), SetupValue::Number(3.0));
+        assert_eq!(
+            SetupValue::from("4 (TC)"),
+            SetupValue::Text("4 (TC)".to_owned())
+        );
+        assert_eq!(
+            SetupValue::from("82C, 85C, 88C"),
+            SetupValue::Text("82C, 85C, 88C".to_owned())
+        );
+    }
+
+    #[test]
+    fn path_lookup() {
+        let tree = setup().tree();
+
+        assert_eq!(
+            tree.value("Chassis.LeftFront.Camber"),
+            Some(&SetupValue::Quantity(Quantity::new(-4.0, Unit::Degrees)))
+        );
+        assert_eq!(
+            tree.value("Chassis.LeftRear.BumpStiffness"),
+            Some(&SetupValue::Clicks(6))
+        );
+        assert_eq!(
+            tree.value("Chassis.Front.ArbBlades"),
+            Some(&SetupValue::Number(3.0))
+        );
+        assert!(matches!(
+            tree.get("TiresAero.LeftFrontTire"),
+            Some(SetupNode::Section(_))
+        ));
+        assert!(tree.value("TiresAero.LeftFrontTire").is_none());
+        assert!(tree.get("Chassis.LeftFront.Camber.Extra").is_none());
+        assert!(tree.get("Chassis.Nowhere").is_none());
+    }
+
+    #[test]
+    fn season_folders() {
+        for name in &[
+            "2021S2", "21S2", "21 S2", "2021 S2", "S2 2021", "Season 2", "21s4",
+        ] {
+            assert!(is_season_folder(name), "{} is a season", name);
+        }
+
+        for name in &["imola", "spa", "S2", "monza 2021", "silverstone", "2021"] {
+            assert!(!is_season_folder(name), "{} is not a season", name);
+        }
+    }
+
+    #[test]
+    fn setup_library() {
+        let root = std::env::temp_dir().join(format!("iracing-setups-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        let write = |path: &str, data: &[u8]| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, data).unwrap();
+        };
+
+        let race = b"race setup".to_vec();
+        write("mercedesamggt3/imola/2021S2/imola race.sto", &race);
+        write("mercedesamggt3/spa/imola race.sto", &race);
+        write("mercedesamggt3/2021S2/imola/quali.sto", b"quali setup");
+        write("ferrari488gt3/imola/imola race.sto", b"ferrari setup");
+        write("mercedesamggt3/imola/notes.txt", b"not a setup");
+        write("loose.sto", &race);
+
+        let library = SetupLibrary::scan(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(library.entries.len(), 4);
+        assert!(library.errors.is_empty());
+
+        let quali = library
+            .entries
+            .iter()
+            .find(|e| e.file_name == "quali.sto")
+            .unwrap();
+        assert_eq!(quali.track.as_deref(), Some("imola"));
+        assert_eq!(quali.season.as_deref(), Some("2021S2"));
+        assert_eq!(quali.size, 11);
+
+        assert_eq!(library.for_car("mercedesamggt3").count(), 3);
+        assert_eq!(library.for_track("mercedesamggt3", "imola").count(), 2);
+        assert_eq!(library.by_hash(content_hash(&race)).count(), 2);
+        assert_eq!(library.duplicates().len(), 1);
+        assert_eq!(library.unique().len(), 3);
+
+        let session: SessionDetails = serde_yaml::from_str(SESSION_YAML).unwrap();
+        let found = library.find_session_setup(&session).unwrap();
+        assert_eq!(found.car, "mercedesamggt3");
+        assert_eq!(found.track.as_deref(), Some("imola"));
+        assert_eq!(found.hash, content_hash(&race));
+    }
+
+    #[test]
+    fn diff_on_update() {
+        let first = setup();
+        let mut second = first.clone();
+        second.update_count += 1;
+
+        let chassis = second
+            .extra
+            .get_mut("Chassis")
+            .and_then(|c| c.as_mapping_mut())
+            .unwrap();
+        let left_front = chassis
+            .get_mut(&YamlValue::from("LeftFront"))
+            .and_then(|c| c.as_mapping_mut())
+            .unwrap();
+        left_front.insert("Camber".into(), "-3.5 deg".into());
+        left_front.remove(&YamlValue::from("ReboundStiffness"));
+        left_front.insert("TireCompound".into(), "Wet".into());
 
-    pub fn from_file(path: &Path) -> io::Result<Self> {
-        let mut file = File::open(path)?;
-        let mut contents = Vec::<u8>::new(); 
-        file.read_to_end(&mut contents)?;
+        let mut watcher = SetupWatcher::new();
+        assert!(watcher.update(&first).is_none());
+        assert!(watcher.update(&first).is_none());
 
-        Setup::new(contents)
+        let changes = watcher.update(&second).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                SetupChange::Changed {
+                    path: "Chassis.LeftFront.Camber".to_owned(),
+                    from: SetupValue::Quantity(Quantity::new(-4.0, Unit::Degrees)),
+                    to: SetupValue::Quantity(Quantity::new(-3.5, Unit::Degrees)),
+                },
+                SetupChange::Removed {
+                    path: "Chassis.LeftFront.ReboundStiffness".to_owned(),
+                    value: SetupValue::Clicks(10),
+                },
+                SetupChange::Added {
+                    path: "Chassis.LeftFront.TireCompound".to_owned(),
+                    value: SetupValue::Text("Wet".to_owned()),
+                },
+            ]
+        );
     }
-}
\ No newline at end of file
+}
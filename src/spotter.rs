@@ -0,0 +1,519 @@
+//!
+//! Spotter callouts.
+//!
+//! Turns the `CarLeftRight` state and the lap distance of every car into a debounced stream of
+//! callouts, as a human spotter would give them.
+
+use crate::states::CarLeftRight;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+use crate::telemetry::{Sample, Value};
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+use std::convert::{TryFrom, TryInto};
+
+/// Closing speeds above this (m/s) are assumed to be a car being reset or towed.
+const MAX_CLOSING_SPEED: f32 = 150.0;
+
+///
+/// Spotter timings and thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpotterConfig {
+    /// Time (s) a change in cars alongside must persist before it is called
+    pub debounce: f64,
+
+    /// Interval (s) between "still there" calls while a car remains alongside
+    pub still_there_interval: f64,
+
+    /// Distance (m) behind the player within which approaching cars are considered
+    pub approach_distance: f32,
+
+    /// Closing speed (m/s) above which an approaching car is called
+    pub approach_speed: f32,
+
+    /// Minimum time (s) between warnings for the same approaching car
+    pub approach_repeat: f64,
+}
+
+impl Default for SpotterConfig {
+    fn default() -> Self {
+        SpotterConfig {
+            debounce: 0.2,
+            still_there_interval: 3.0,
+            approach_distance: 100.0,
+            approach_speed: 8.0,
+            approach_repeat: 10.0,
+        }
+    }
+}
+
+///
+/// A single spotter callout
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Callout {
+    CarLeft,
+    CarRight,
+    ThreeWide,
+    ClearLeft,
+    ClearRight,
+    StillThere,
+    /// A car is closing quickly from behind
+    ApproachingFast {
+        car_idx: usize,
+        /// Distance behind the player (m)
+        gap: f32,
+        /// Closing speed (m/s)
+        closing_speed: f32,
+    },
+}
+
+///
+/// A callout and the session time at which it was made
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimedCallout {
+    pub session_time: f64,
+
+    #[serde(flatten)]
+    pub callout: Callout,
+}
+
+///
+/// Telemetry used by the spotter for a single tick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpotterFrame {
+    pub session_time: f64,
+    pub car_left_right: CarLeftRight,
+    pub player_car_idx: usize,
+    /// Lap distance percentage of every car (`CarIdxLapDistPct`), negative if not on track
+    pub lap_dist_pct: Vec<f32>,
+}
+
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+impl TryFrom<&Sample> for SpotterFrame {
+    type Error = String;
+
+    fn try_from(sample: &Sample) -> Result<Self, Self::Error> {
+        let player: i32 = sample.get("PlayerCarIdx")?.try_into()?;
+
+        Ok(SpotterFrame {
+            session_time: sample.get("SessionTime")?.try_into()?,
+            car_left_right: sample.car_left_right()?,
+            player_car_idx: player.max(0) as usize,
+            lap_dist_pct: match sample.get("CarIdxLapDistPct")? {
+                Value::FloatVec(v) => v,
+                _ => return Err("CarIdxLapDistPct: Value is not a float array".to_owned()),
+            },
+        })
+    }
+}
+
+///
+/// Spotter
+///
+/// Feed it a `SpotterFrame` per telemetry tick with `update()`, or a recorded sequence
+/// with `run()`.
+///
+/// # Examples
+///
+/// ```
+/// use iracing::spotter::{Callout, Spotter, SpotterConfig, SpotterFrame};
+/// use iracing::states::CarLeftRight;
+///
+/// let mut spotter = Spotter::new(SpotterConfig::default(), 4860.0);
+///
+/// let frame = |t: f64, clr: CarLeftRight| SpotterFrame {
+///     session_time: t,
+///     car_left_right: clr,
+///     player_car_idx: 0,
+///     lap_dist_pct: vec![0.5],
+/// };
+///
+/// let callouts = spotter.run(vec![
+///     frame(0.0, CarLeftRight::Clear),
+///     frame(0.1, CarLeftRight::CarLeft),
+///     frame(0.4, CarLeftRight::CarLeft),
+/// ]);
+///
+/// assert_eq!(callouts[0].callout, Callout::CarLeft);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Spotter {
+    config: SpotterConfig,
+    track_length: f32,
+
+    confirmed: CarLeftRight,
+    pending: Option<(CarLeftRight, f64)>,
+    last_call: f64,
+
+    previous: Option<SpotterFrame>,
+    gaps: HashMap<usize, f32>,
+    approach_warned: HashMap<usize, f64>,
+}
+
+impl Spotter {
+    /// Create a new spotter for a track of `track_length` metres
+    pub fn new(config: SpotterConfig, track_length: f32) -> Self {
+        Spotter {
+            config,
+            track_length,
+            confirmed: CarLeftRight::Clear,
+            pending: None,
+            last_call: 0.0,
+            previous: None,
+            gaps: HashMap::new(),
+            approach_warned: HashMap::new(),
+        }
+    }
+
+    /// Process a recorded sequence of frames, returning every callout made.
+    pub fn run<I: IntoIterator<Item = SpotterFrame>>(&mut self, frames: I) -> Vec<TimedCallout> {
+        frames
+            .into_iter()
+            .flat_map(|frame| self.update(&frame))
+            .collect()
+    }
+
+    /// Process a single frame, returning callouts to be made.
+    pub fn update(&mut self, frame: &SpotterFrame) -> Vec<TimedCallout> {
+        let mut callouts = self.alongside(frame);
+        callouts.extend(self.approaching(frame));
+        self.previous = Some(frame.clone());
+
+        callouts
+            .into_iter()
+            .map(|callout| TimedCallout {
+                session_time: frame.session_time,
+                callout,
+            })
+            .collect()
+    }
+
+    fn alongside(&mut self, frame: &SpotterFrame) -> Vec<Callout> {
+        let now = frame.session_time;
+        let clr = frame.car_left_right;
+
+        // No spotter information (e.g. not in the car), forget about anything alongside.
+        if matches!(clr, CarLeftRight::Off | CarLeftRight::Unknown(_)) {
+            self.confirmed = CarLeftRight::Clear;
+            self.pending = None;
+            return Vec::new();
+        }
+
+        if clr == self.confirmed {
+            self.pending = None;
+
+            if (clr.left() || clr.right())
+                && now - self.last_call >= self.config.still_there_interval
+            {
+                self.last_call = now;
+                return vec![Callout::StillThere];
+            }
+
+            return Vec::new();
+        }
+
+        let since = match self.pending {
+            Some((state, since)) if state == clr => since,
+            _ => {
+                self.pending = Some((clr, now));
+                now
+            }
+        };
+
+        if now - since < self.config.debounce {
+            return Vec::new();
+        }
+
+        let previous = self.confirmed;
+        self.confirmed = clr;
+        self.pending = None;
+        self.last_call = now;
+
+        let mut callouts = Vec::new();
+
+        // Two cars on one side is three wide as well as a car either side
+        if clr.three_wide() {
+            if !previous.three_wide() {
+                callouts.push(Callout::ThreeWide);
+            }
+        } else {
+            if clr.left() && !previous.left() {
+                callouts.push(Callout::CarLeft);
+            }
+            if clr.right() && !previous.right() {
+                callouts.push(Callout::CarRight);
+            }
+        }
+
+        if previous.left() && !clr.left() {
+            callouts.push(Callout::ClearLeft);
+        }
+        if previous.right() && !clr.right() {
+            callouts.push(Callout::ClearRight);
+        }
+
+        callouts
+    }
+
+    fn approaching(&mut self, frame: &SpotterFrame) -> Vec<Callout> {
+        let mut callouts = Vec::new();
+
+        let player = match frame.lap_dist_pct.get(frame.player_car_idx) {
+            Some(&pct) if pct >= 0.0 => pct,
+            _ => {
+                self.gaps.clear();
+                return callouts;
+            }
+        };
+
+        let dt = self
+            .previous
+            .as_ref()
+            .map(|p| (frame.session_time - p.session_time) as f32)
+            .unwrap_or(0.0);
+
+        let mut gaps = HashMap::new();
+
+        for (car_idx, &pct) in frame.lap_dist_pct.iter().enumerate() {
+            if car_idx == frame.player_car_idx || pct < 0.0 {
+                continue;
+            }
+
+            let gap = (player - pct).rem_euclid(1.0) * self.track_length;
+
+            if gap > self.config.approach_distance {
+                continue;
+            }
+
+            gaps.insert(car_idx, gap);
+
+            let previous_gap = match self.gaps.get(&car_idx) {
+                Some(&g) if dt > 0.0 => g,
+                _ => continue,
+            };
+
+            let closing_speed = (previous_gap - gap) / dt;
+
+            if closing_speed < self.config.approach_speed || closing_speed > MAX_CLOSING_SPEED {
+                continue;
+            }
+
+            let recently_warned = matches!(
+                self.approach_warned.get(&car_idx),
+                Some(&t) if frame.session_time - t < self.config.approach_repeat
+            );
+
+            if !recently_warned {
+                self.approach_warned.insert(car_idx, frame.session_time);
+                callouts.push(Callout::ApproachingFast {
+                    car_idx,
+                    gap,
+                    closing_speed,
+                });
+            }
+        }
+
+        self.gaps = gaps;
+        callouts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a recording at 8Hz from a list of `CarLeftRight` states
+    fn recording(states: &[CarLeftRight]) -> Vec<SpotterFrame> {
+        states
+            .iter()
+            .enumerate()
+            .map(|(i, &clr)| SpotterFrame {
+                session_time: i as f64 / 8.0,
+                car_left_right: clr,
+                player_car_idx: 0,
+                lap_dist_pct: vec![0.5],
+            })
+            .collect()
+    }
+
+    fn config() -> SpotterConfig {
+        SpotterConfig {
+            debounce: 0.25,
+            ..SpotterConfig::default()
+        }
+    }
+
+    fn callouts(spotter: &mut Spotter, frames: Vec<SpotterFrame>) -> Vec<(f64, Callout)> {
+        spotter
+            .run(frames)
+            .into_iter()
+            .map(|c| (c.session_time, c.callout))
+            .collect()
+    }
+
+    #[test]
+    fn debounced_callouts() {
+        use CarLeftRight::{CarLeft, CarRight, Clear};
+
+        let mut spotter = Spotter::new(config(), 1000.0);
+        let frames = recording(&[
+            Clear,
+            CarLeft,
+            Clear, // Flicker, ignored
+            CarLeft,
+            CarLeft,
+            CarLeft,
+            CarLeftRight::CarLeftRight,
+            CarLeftRight::CarLeftRight,
+            CarLeftRight::CarLeftRight,
+            CarRight,
+            CarRight,
+            CarRight,
+            Clear,
+            Clear,
+            Clear,
+        ]);
+
+        assert_eq!(
+            callouts(&mut spotter, frames),
+            vec![
+                (0.625, Callout::CarLeft),
+                (1.0, Callout::ThreeWide),
+                (1.375, Callout::ClearLeft),
+                (1.75, Callout::ClearRight),
+            ]
+        );
+    }
+
+    #[test]
+    fn two_cars_one_side() {
+        use CarLeftRight::{CarLeft, CarRight, Clear, TwoCarsLeft, TwoCarsRight};
+
+        let mut spotter = Spotter::new(config(), 1000.0);
+        let frames = recording(&[
+            Clear,
+            CarLeft,
+            CarLeft,
+            CarLeft,
+            TwoCarsLeft,
+            TwoCarsLeft,
+            TwoCarsLeft,
+            CarLeft,
+            CarLeft,
+            CarLeft,
+            Clear,
+            Clear,
+            Clear,
+        ]);
+
+        assert_eq!(
+            callouts(&mut spotter, frames),
+            vec![
+                (0.375, Callout::CarLeft),
+                (0.75, Callout::ThreeWide),
+                (1.5, Callout::ClearLeft),
+            ]
+        );
+
+        let mut spotter = Spotter::new(config(), 1000.0);
+        let frames = recording(&[
+            CarRight,
+            CarRight,
+            CarRight,
+            TwoCarsRight,
+            TwoCarsRight,
+            TwoCarsRight,
+            CarLeft,
+            CarLeft,
+            CarLeft,
+        ]);
+
+        assert_eq!(
+            callouts(&mut spotter, frames),
+            vec![
+                (0.25, Callout::CarRight),
+                (0.625, Callout::ThreeWide),
+                (1.0, Callout::CarLeft),
+                (1.0, Callout::ClearRight),
+            ]
+        );
+    }
+
+    #[test]
+    fn still_there() {
+        let mut spotter = Spotter::new(config(), 1000.0);
+        let frames = recording(&[CarLeftRight::CarRight; 70]);
+
+        let calls = callouts(&mut spotter, frames);
+        assert_eq!(calls[0], (0.25, Callout::CarRight));
+        assert_eq!(calls[1], (3.25, Callout::StillThere));
+        assert_eq!(calls[2], (6.25, Callout::StillThere));
+        assert_eq!(calls.len(), 3);
+    }
+
+    #[test]
+    fn approaching_fast() {
+        let mut spotter = Spotter::new(SpotterConfig::default(), 1000.0);
+
+        // Player at 50m/s, car 1 at 65m/s starting 120m behind, car 2 at 50m/s 50m behind.
+        let frames: Vec<SpotterFrame> = (0..40)
+            .map(|i| {
+                let t = i as f32 / 10.0;
+                SpotterFrame {
+                    session_time: t as f64,
+                    car_left_right: CarLeftRight::Clear,
+                    player_car_idx: 0,
+                    lap_dist_pct: vec![
+                        (0.05 + 0.05 * t).rem_euclid(1.0),
+                        (0.93 + 0.065 * t).rem_euclid(1.0),
+                        (0.0 + 0.05 * t).rem_euclid(1.0),
+                    ],
+                }
+            })
+            .collect();
+
+        let calls = spotter.run(frames);
+
+        assert_eq!(calls.len(), 1);
+        match calls[0].callout {
+            Callout::ApproachingFast {
+                car_idx,
+                gap,
+                closing_speed,
+            } => {
+                assert_eq!(car_idx, 1);
+                assert!(gap <= 100.0);
+                assert!((closing_speed - 15.0).abs() < 0.5);
+            }
+            other => panic!("Unexpected callout {:?}", other),
+        }
+    }
+
+    #[cfg(all(target_os = "windows", feature = "telemetry"))]
+    #[test]
+    fn frame_from_sample() {
+        use crate::telemetry::fixture::{self, Var, BITS, INT};
+
+        // Types and units as iRacing reports them
+        let sample = fixture::sample(vec![
+            Var::double("SessionTime", "s", 1520.25),
+            Var::ints("PlayerCarIdx", INT, &[2]),
+            Var::ints("CarLeftRight", BITS, &[5]),
+            Var::floats("CarIdxLapDistPct", "%", &[0.25, -1.0, 0.5]),
+        ]);
+
+        let frame = SpotterFrame::try_from(&sample).unwrap();
+
+        assert_eq!(
+            frame,
+            SpotterFrame {
+                session_time: 1520.25,
+                car_left_right: CarLeftRight::TwoCarsLeft,
+                player_car_idx: 2,
+                lap_dist_pct: vec![0.25, -1.0, 0.5],
+            }
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SessionState {
     Invalid(i32),
     GetInCar,
@@ -93,7 +94,7 @@ bitflags! {
 }
 
 bitflags! {
-    #[derive(Default)]
+    #[derive(Default, Serialize, Deserialize)]
     pub struct Flags: u32 {
         const CHECKERED_FLAG = 0x01;
         const WHITE_FLAG = 1 << 1;
@@ -125,37 +126,66 @@ bitflags! {
     }
 }
 
+bitflags! {
+    ///
+    /// Pacing status of a car under caution (`CarIdxPaceFlags`)
+    #[derive(Default, Serialize, Deserialize)]
+    pub struct PaceFlags: u32 {
+        const END_OF_LINE = 0x01;
+        const FREE_PASS = 0x02;
+        const WAVED_AROUND = 0x04;
+    }
+}
+
+/// Implements `From<u32>` for a bitfield, retaining any bits which are not (yet) known.
+macro_rules! from_bits_retained {
+    ($($t:ty),*) => {
+        $(
+            impl From<u32> for $t {
+                fn from(bits: u32) -> Self {
+                    // Safety: bitflags only requires that unknown bits are not relied upon,
+                    // all operations on the flags remain well defined.
+                    unsafe { Self::from_bits_unchecked(bits) }
+                }
+            }
+        )*
+    };
+}
+
+from_bits_retained!(EngineWarnings, CameraState, PitServices, Flags, PaceFlags);
+
 /**
  * Action which will be initiated by the "RESET" button
  */
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 pub enum ResetAction {
+    #[default]
     Enter,
     Exit,
     Reset,
 }
 
-impl Default for ResetAction {
-    fn default() -> Self {
-        Self::Enter
+impl From<i32> for ResetAction {
+    /// Unrecognised values are treated as `Enter`.
+    fn from(v: i32) -> ResetAction {
+        match v {
+            1 => Self::Exit,
+            2 => Self::Reset,
+            _ => Self::Enter,
+        }
     }
 }
 
 /**
  * Current units being displayed
  */
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 pub enum Units {
     Imperial,
+    #[default]
     Metric,
 }
 
-impl Default for Units {
-    fn default() -> Self {
-        Self::Metric
-    }
-}
-
 impl From<i32> for Units {
     fn from(v: i32) -> Units {
         if v > 0 {
@@ -165,3 +195,152 @@ impl From<i32> for Units {
         }
     }
 }
+
+/**
+ * Where a car is in relation to the track (`irsdk_TrkLoc`)
+ *
+ * Not to be confused with `TrackSurface`, which is the material under the car.
+ */
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrackLocation {
+    #[default]
+    NotInWorld,
+    OffTrack,
+    InPitStall,
+    ApproachingPits,
+    OnTrack,
+    Unknown(i32),
+}
+
+impl From<i32> for TrackLocation {
+    fn from(v: i32) -> TrackLocation {
+        match v {
+            -1 => Self::NotInWorld,
+            0 => Self::OffTrack,
+            1 => Self::InPitStall,
+            2 => Self::ApproachingPits,
+            3 => Self::OnTrack,
+            _ => Self::Unknown(v),
+        }
+    }
+}
+
+/**
+ * Status of the player's pit stop (`irsdk_PitSvStatus`)
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PitServiceStatus {
+    None,
+    InProgress,
+    Complete,
+    TooFarLeft,
+    TooFarRight,
+    TooFarForward,
+    TooFarBack,
+    BadAngle,
+    CantFixThat,
+    Unknown(i32),
+}
+
+impl From<i32> for PitServiceStatus {
+    fn from(v: i32) -> PitServiceStatus {
+        match v {
+            0 => Self::None,
+            1 => Self::InProgress,
+            2 => Self::Complete,
+            100 => Self::TooFarLeft,
+            101 => Self::TooFarRight,
+            102 => Self::TooFarForward,
+            103 => Self::TooFarBack,
+            104 => Self::BadAngle,
+            105 => Self::CantFixThat,
+            _ => Self::Unknown(v),
+        }
+    }
+}
+
+impl PitServiceStatus {
+    /// Whether the status indicates the car could not be serviced
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Self::None | Self::InProgress | Self::Complete)
+    }
+}
+
+/**
+ * Pacing mode of the player's car under caution or before the start (`irsdk_PaceMode`)
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaceMode {
+    SingleFileStart,
+    DoubleFileStart,
+    SingleFileRestart,
+    DoubleFileRestart,
+    NotPacing,
+    Unknown(i32),
+}
+
+impl From<i32> for PaceMode {
+    fn from(v: i32) -> PaceMode {
+        match v {
+            0 => Self::SingleFileStart,
+            1 => Self::DoubleFileStart,
+            2 => Self::SingleFileRestart,
+            3 => Self::DoubleFileRestart,
+            4 => Self::NotPacing,
+            _ => Self::Unknown(v),
+        }
+    }
+}
+
+/**
+ * Cars alongside the player, as reported by the spotter (`irsdk_CarLeftRight`)
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CarLeftRight {
+    Off,
+    Clear,
+    CarLeft,
+    CarRight,
+    CarLeftRight,
+    TwoCarsLeft,
+    TwoCarsRight,
+    Unknown(i32),
+}
+
+impl From<i32> for CarLeftRight {
+    fn from(v: i32) -> CarLeftRight {
+        match v {
+            0 => Self::Off,
+            1 => Self::Clear,
+            2 => Self::CarLeft,
+            3 => Self::CarRight,
+            4 => Self::CarLeftRight,
+            5 => Self::TwoCarsLeft,
+            6 => Self::TwoCarsRight,
+            _ => Self::Unknown(v),
+        }
+    }
+}
+
+impl CarLeftRight {
+    /// Whether there is at least one car to the left
+    pub fn left(&self) -> bool {
+        matches!(self, Self::CarLeft | Self::CarLeftRight | Self::TwoCarsLeft)
+    }
+
+    /// Whether there is at least one car to the right
+    pub fn right(&self) -> bool {
+        matches!(
+            self,
+            Self::CarRight | Self::CarLeftRight | Self::TwoCarsRight
+        )
+    }
+
+    /// Whether the player is three wide: between two cars, or with two cars on one side
+    pub fn three_wide(&self) -> bool {
+        matches!(
+            self,
+            Self::CarLeftRight | Self::TwoCarsLeft | Self::TwoCarsRight
+        )
+    }
+}
@@ -1,7 +1,9 @@
+use crate::quantity::{Quantity, Unit};
 use crate::session::*;
+use crate::states::*;
+use crate::track_surface::TrackSurface;
 use encoding_rs::mem::decode_latin1;
 use serde::{Deserialize, Serialize};
-use serde_yaml::from_str as yaml_from;
 use std::convert::TryInto;
 use std::default::Default;
 use std::error::Error;
@@ -23,11 +25,7 @@ use winapi::um::synchapi::{CreateEventW, ResetEvent, WaitForSingleObject};
 /// System path where the shared memory map is located.
 pub const TELEMETRY_PATH: &str = r"Local\IRSDKMemMapFileName";
 
-/// Magic number specifying an unlimited number of laps
-pub const UNLIMITED_LAPS: i32 = 32767;
-
-/// Magic number specifying unlimited time
-pub const UNLIMITED_TIME: f32 = 604800.0;
+pub use crate::session::{UNLIMITED_LAPS, UNLIMITED_TIME};
 
 const DATA_EVENT_NAME: &str = r"Local\IRSDKDataValidEvent";
 
@@ -162,10 +160,20 @@ pub enum Value {
     DOUBLE(f64),
     UNKNOWN(()),
     IntVec(Vec<i32>),
+    BitsVec(Vec<u32>),
     FloatVec(Vec<f32>),
     BoolVec(Vec<bool>),
 }
 
+impl ValueDescription {
+    /// The value along with its unit, if it is a single number.
+    pub fn quantity(&self) -> Option<Quantity> {
+        self.value
+            .as_f64()
+            .map(|v| Quantity::new(v, Unit::telemetry(&self.unit)))
+    }
+}
+
 impl From<i32> for Value {
     fn from(v: i32) -> Value {
         match v {
@@ -181,12 +189,30 @@ impl From<i32> for Value {
 }
 
 impl Value {
+    /// Get a single numeric value as an `f64`.
+    ///
+    /// Returns `None` for arrays and unknown values.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::CHAR(c) => Some(c as f64),
+            Self::BOOL(b) => Some(if b { 1.0 } else { 0.0 }),
+            Self::INT(i) => Some(i as f64),
+            Self::BITS(u) => Some(u as f64),
+            Self::FLOAT(f) => Some(f as f64),
+            Self::DOUBLE(d) => Some(d),
+            _ => None,
+        }
+    }
+
     pub fn size(&self) -> usize {
         match self {
             Self::CHAR(_) | Self::BOOL(_) | Self::BoolVec(_) => 1,
-            Self::INT(_) | Self::BITS(_) | Self::FLOAT(_) | Self::IntVec(_) | Self::FloatVec(_) => {
-                4
-            }
+            Self::INT(_)
+            | Self::BITS(_)
+            | Self::FLOAT(_)
+            | Self::IntVec(_)
+            | Self::BitsVec(_)
+            | Self::FloatVec(_) => 4,
             Self::DOUBLE(_) => 8,
             Self::UNKNOWN(_) => 1,
         }
@@ -425,6 +451,162 @@ impl Sample {
         }
     }
 
+    ///
+    /// Get a value from the sample along with its unit.
+    ///
+    /// Returns `Err(String)` if the value cannot be found, or is not a single number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use iracing::telemetry::Connection;
+    ///
+    /// let sample = Connection::new()?.telemetry()?;
+    /// let units = sample.display_units()?;
+    ///
+    /// println!("{}", sample.quantity("Speed")?.format(units, 0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn quantity(&self, name: &'static str) -> Result<Quantity, String> {
+        let vh = self
+            .header_for(name)
+            .ok_or_else(|| format!("No value '{}' found", name))?;
+
+        match self.value(&vh).as_f64() {
+            Some(v) => Ok(Quantity::new(v, Unit::telemetry(&vh.unit()))),
+            None => Err(format!("{}: Value is not a single number", name)),
+        }
+    }
+
+    fn int(&self, name: &'static str) -> Result<i32, String> {
+        self.get(name)?
+            .try_into()
+            .map_err(|e: &str| format!("{}: {}", name, e))
+    }
+
+    fn bits(&self, name: &'static str) -> Result<u32, String> {
+        self.get(name)?
+            .try_into()
+            .map_err(|e: &str| format!("{}: {}", name, e))
+    }
+
+    ///
+    /// Current state of the session (`SessionState`)
+    pub fn session_state(&self) -> Result<SessionState, String> {
+        self.int("SessionState").map(SessionState::from)
+    }
+
+    ///
+    /// Laps and time remaining in the session (`SessionLapsRemainEx`, `SessionTimeRemain`)
+    ///
+    /// Check this against the session's `Session::limit()` with `SessionLimit::is_consistent_with`.
+    pub fn session_remaining(&self) -> Result<SessionLimit, String> {
+        let laps = self.int("SessionLapsRemainEx")?;
+        let time = self
+            .get("SessionTimeRemain")?
+            .as_f64()
+            .ok_or_else(|| "SessionTimeRemain: Value is not a single number".to_owned())?;
+
+        Ok(SessionLimit::from_remaining(laps, time))
+    }
+
+    ///
+    /// Flags currently shown for the session (`SessionFlags`)
+    ///
+    /// Bits which are not known to `Flags` are retained.
+    pub fn session_flags(&self) -> Result<Flags, String> {
+        self.bits("SessionFlags").map(Flags::from)
+    }
+
+    ///
+    /// Warnings for the player's engine (`EngineWarnings`)
+    pub fn engine_warnings(&self) -> Result<EngineWarnings, String> {
+        self.bits("EngineWarnings").map(EngineWarnings::from)
+    }
+
+    ///
+    /// State of the camera (`CamCameraState`)
+    pub fn camera_state(&self) -> Result<CameraState, String> {
+        self.bits("CamCameraState").map(CameraState::from)
+    }
+
+    ///
+    /// Services requested for the next pit stop (`PitSvFlags`)
+    pub fn pit_services(&self) -> Result<PitServices, String> {
+        self.bits("PitSvFlags").map(PitServices::from)
+    }
+
+    ///
+    /// Units the player has chosen to display (`DisplayUnits`)
+    pub fn display_units(&self) -> Result<Units, String> {
+        self.int("DisplayUnits").map(Units::from)
+    }
+
+    ///
+    /// Action the reset button will currently perform (`EnterExitReset`)
+    pub fn enter_exit_reset(&self) -> Result<ResetAction, String> {
+        self.int("EnterExitReset").map(ResetAction::from)
+    }
+
+    fn ints(&self, name: &'static str) -> Result<Vec<i32>, String> {
+        match self.get(name)? {
+            Value::IntVec(v) => Ok(v),
+            _ => Err(format!("{}: Value is not an integer array", name)),
+        }
+    }
+
+    fn bits_vec(&self, name: &'static str) -> Result<Vec<u32>, String> {
+        match self.get(name)? {
+            Value::BitsVec(v) => Ok(v),
+            Value::IntVec(v) => Ok(v.into_iter().map(|i| i as u32).collect()),
+            _ => Err(format!("{}: Value is not a bitfield array", name)),
+        }
+    }
+
+    ///
+    /// Location of each car relative to the track, indexed by car index (`CarIdxTrackSurface`)
+    pub fn car_idx_track_surface(&self) -> Result<Vec<TrackLocation>, String> {
+        self.ints("CarIdxTrackSurface")
+            .map(|v| v.into_iter().map(TrackLocation::from).collect())
+    }
+
+    ///
+    /// Surface material under each car, indexed by car index (`CarIdxTrackSurfaceMaterial`)
+    pub fn car_idx_track_surface_material(&self) -> Result<Vec<TrackSurface>, String> {
+        self.ints("CarIdxTrackSurfaceMaterial")
+            .map(|v| v.into_iter().map(TrackSurface::from).collect())
+    }
+
+    ///
+    /// Status of the player's pit stop (`PlayerCarPitSvStatus`)
+    pub fn pit_service_status(&self) -> Result<PitServiceStatus, String> {
+        self.int("PlayerCarPitSvStatus").map(PitServiceStatus::from)
+    }
+
+    ///
+    /// Pacing mode of the player's car (`PaceMode`)
+    pub fn pace_mode(&self) -> Result<PaceMode, String> {
+        self.int("PaceMode").map(PaceMode::from)
+    }
+
+    ///
+    /// Pacing flags of each car, indexed by car index (`CarIdxPaceFlags`)
+    pub fn car_idx_pace_flags(&self) -> Result<Vec<PaceFlags>, String> {
+        self.bits_vec("CarIdxPaceFlags")
+            .map(|v| v.into_iter().map(PaceFlags::from).collect())
+    }
+
+    ///
+    /// Cars alongside the player (`CarLeftRight`)
+    ///
+    /// iRacing reports this as a bitfield, older SDK versions as an integer; both are accepted.
+    pub fn car_left_right(&self) -> Result<CarLeftRight, String> {
+        self.bits("CarLeftRight")
+            .map(|v| CarLeftRight::from(v as i32))
+    }
+
     fn value(&self, vh: &ValueHeader) -> Value {
         let vs = vh.offset as usize; // Value start
         let vt = Value::from(vh.value_type);
@@ -441,7 +623,7 @@ impl Sample {
                     Value::INT(i32::from_le_bytes(raw_val.try_into().unwrap()))
                 } else {
                     let mut values: Vec<i32> = Vec::with_capacity(vc);
-                    for i in 0..vc - 1 {
+                    for i in 0..vc {
                         values.push(i32::from_le_bytes(
                             self.buffer[vs + vz * i..vs + vz * (i + 1)]
                                 .try_into()
@@ -458,7 +640,7 @@ impl Sample {
                 } else {
                     let mut values: Vec<f32> = Vec::with_capacity(vc);
 
-                    for i in 0..vc - 1 {
+                    for i in 0..vc {
                         values.push(f32::from_le_bytes(
                             self.buffer[vs + vz * i..vs + vz * (i + 1)]
                                 .try_into()
@@ -470,7 +652,23 @@ impl Sample {
                 }
             }
             Value::DOUBLE(_) => Value::DOUBLE(f64::from_le_bytes(raw_val.try_into().unwrap())),
-            Value::BITS(_) => Value::BITS(u32::from_le_bytes(raw_val.try_into().unwrap())),
+            Value::BITS(_) => {
+                if vc == 1 {
+                    Value::BITS(u32::from_le_bytes(raw_val.try_into().unwrap()))
+                } else {
+                    let mut values: Vec<u32> = Vec::with_capacity(vc);
+
+                    for i in 0..vc {
+                        values.push(u32::from_le_bytes(
+                            self.buffer[vs + vz * i..vs + vz * (i + 1)]
+                                .try_into()
+                                .unwrap(),
+                        ));
+                    }
+
+                    Value::BitsVec(values)
+                }
+            }
             Value::CHAR(_) => Value::CHAR(raw_val[0] as u8),
             Value::BOOL(_) => {
                 if vc == 1 {
@@ -478,7 +676,7 @@ impl Sample {
                 } else {
                     let mut values: Vec<bool> = Vec::with_capacity(vc);
 
-                    for i in 0..vc - 1 {
+                    for i in 0..vc {
                         values.push(self.buffer[vs + i] > 0);
                     }
 
@@ -691,6 +889,26 @@ impl Connection {
     /// };
     /// ```
     pub fn session_info(&mut self) -> Result<SessionDetails, Box<dyn std::error::Error>> {
+        let details = SessionDetails::from_yaml(&self.session_yaml())?;
+
+        Ok(details)
+    }
+
+    ///
+    /// Get session information, skipping any values which can't be parsed
+    ///
+    /// Returns the session details along with a warning for each value skipped.
+    /// See `SessionDetails::from_yaml_lenient`.
+    pub fn session_info_lenient(
+        &mut self,
+    ) -> Result<(SessionDetails, Vec<SessionWarning>), Box<dyn std::error::Error>> {
+        let parsed = SessionDetails::from_yaml_lenient(&self.session_yaml())?;
+
+        Ok(parsed)
+    }
+
+    /// Raw session info YAML
+    fn session_yaml(&self) -> String {
         let header = unsafe { Self::read_header(self.location) };
 
         let start = (self.location as usize + header.session_info_offset as usize) as *const u8;
@@ -699,10 +917,7 @@ impl Connection {
         let data: &[u8] = unsafe { from_raw_parts(start, size) };
 
         // Decode the data as Latin-1 (Rust wants UTF-8)
-        let content = decode_latin1(data);
-        let details = yaml_from(&content)?;
-
-        Ok(details)
+        decode_latin1(data).into_owned()
     }
 
     ///
@@ -760,6 +975,89 @@ impl Connection {
     }
 }
 
+/// Builds telemetry samples laid out as iRacing does, without a running simulator.
+#[cfg(test)]
+pub(crate) mod fixture {
+    use super::{Sample, ValueHeader};
+    use std::os::raw::c_char;
+
+    /// irsdk variable types
+    pub const INT: i32 = 2;
+    pub const BITS: i32 = 3;
+    pub const FLOAT: i32 = 4;
+    pub const DOUBLE: i32 = 5;
+
+    /// A variable: name, unit, irsdk type and the little-endian bytes of its values
+    pub struct Var {
+        pub name: &'static str,
+        pub unit: &'static str,
+        pub value_type: i32,
+        pub count: i32,
+        pub data: Vec<u8>,
+    }
+
+    impl Var {
+        pub fn ints(name: &'static str, value_type: i32, values: &[i32]) -> Self {
+            Var {
+                name,
+                unit: "",
+                value_type,
+                count: values.len() as i32,
+                data: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            }
+        }
+
+        pub fn floats(name: &'static str, unit: &'static str, values: &[f32]) -> Self {
+            Var {
+                name,
+                unit,
+                value_type: FLOAT,
+                count: values.len() as i32,
+                data: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            }
+        }
+
+        pub fn double(name: &'static str, unit: &'static str, value: f64) -> Self {
+            Var {
+                name,
+                unit,
+                value_type: DOUBLE,
+                count: 1,
+                data: value.to_le_bytes().to_vec(),
+            }
+        }
+    }
+
+    fn fixed<const N: usize>(s: &str) -> [c_char; N] {
+        let mut out = [0; N];
+        out.iter_mut()
+            .zip(s.bytes().take(N - 1))
+            .for_each(|(o, b)| *o = b as c_char);
+        out
+    }
+
+    pub fn sample(vars: Vec<Var>) -> Sample {
+        let mut headers = vec![];
+        let mut buffer = vec![];
+
+        for var in vars {
+            let mut header = ValueHeader {
+                value_type: var.value_type,
+                offset: buffer.len() as i32,
+                count: var.count,
+                ..ValueHeader::default()
+            };
+            header._name = fixed(var.name);
+            header._unit = fixed(var.unit);
+
+            buffer.extend_from_slice(&var.data);
+            headers.push(header);
+        }
+
+        Sample::new(1, headers, buffer)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -784,4 +1082,39 @@ mod tests {
             .unwrap();
         assert!(session_tick > 0);
     }
+
+    #[test]
+    fn test_session_state() {
+        let state = Connection::new()
+            .expect("Unable to open telemetry")
+            .telemetry()
+            .expect("Couldn't get latest telem")
+            .session_state();
+        assert!(state.is_ok());
+    }
+
+    #[test]
+    fn bitfield_values() {
+        use fixture::{Var, BITS, INT};
+
+        let sample = fixture::sample(vec![
+            Var::ints("CarLeftRight", BITS, &[4]),
+            Var::ints("CarIdxPaceFlags", BITS, &[0, 0x04, 0x4000]),
+            Var::ints("PaceMode", INT, &[4]),
+        ]);
+
+        assert_eq!(sample.car_left_right(), Ok(CarLeftRight::CarLeftRight));
+        assert!(matches!(
+            sample.get("CarIdxPaceFlags"),
+            Ok(Value::BitsVec(v)) if v == [0, 0x04, 0x4000]
+        ));
+
+        let flags = sample.car_idx_pace_flags().unwrap();
+        assert_eq!(flags.len(), 3);
+        assert_eq!(flags[1], PaceFlags::from(0x04));
+        assert_eq!(flags[2].bits(), 0x4000); // Unknown bits are kept
+
+        let sample = fixture::sample(vec![Var::ints("CarLeftRight", INT, &[2])]);
+        assert_eq!(sample.car_left_right(), Ok(CarLeftRight::CarLeft));
+    }
 }
@@ -0,0 +1,541 @@
+//!
+//! Tire temperature, wear and pressure model.
+//!
+//! iRacing exports per-corner tire data over a large number of individual channels
+//! (`LFtempCL`, `LFwearM`, `LFcoldPressure`, ...). This module groups them into a typed
+//! `Tires` model, and provides some basic analysis on top of it.
+//!
+//! Note that iRacing only updates tire temperatures and wear when the car is in the pits.
+
+use serde::{Deserialize, Serialize};
+use std::ops::Index;
+
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+use crate::telemetry::Sample;
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+use std::convert::{TryFrom, TryInto};
+
+///
+/// Corner of the car a tire is fitted to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Corner {
+    LeftFront,
+    RightFront,
+    LeftRear,
+    RightRear,
+}
+
+///
+/// Band across the width of the tire, as seen from the driver's seat.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Band {
+    Left,
+    Middle,
+    Right,
+}
+
+impl Corner {
+    /// All corners, in the order used by iRacing.
+    pub const ALL: [Corner; 4] = [
+        Corner::LeftFront,
+        Corner::RightFront,
+        Corner::LeftRear,
+        Corner::RightRear,
+    ];
+
+    /// Channel prefix used for this corner (e.g. `LF`)
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::LeftFront => "LF",
+            Self::RightFront => "RF",
+            Self::LeftRear => "LR",
+            Self::RightRear => "RR",
+        }
+    }
+
+    /// The band of the tire closest to the centre-line of the car.
+    pub fn inside_band(self) -> Band {
+        match self {
+            Self::LeftFront | Self::LeftRear => Band::Right,
+            Self::RightFront | Self::RightRear => Band::Left,
+        }
+    }
+
+    /// The band of the tire furthest from the centre-line of the car.
+    pub fn outside_band(self) -> Band {
+        match self.inside_band() {
+            Band::Left => Band::Right,
+            _ => Band::Left,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::LeftFront => 0,
+            Self::RightFront => 1,
+            Self::LeftRear => 2,
+            Self::RightRear => 3,
+        }
+    }
+}
+
+impl Band {
+    /// All bands, from left to right.
+    pub const ALL: [Band; 3] = [Band::Left, Band::Middle, Band::Right];
+
+    fn index(self) -> usize {
+        match self {
+            Self::Left => 0,
+            Self::Middle => 1,
+            Self::Right => 2,
+        }
+    }
+}
+
+///
+/// State of a single tire.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tire {
+    /// Carcass temperature (degC) per band, left to right.
+    pub temperature: [f32; 3],
+
+    /// Remaining tread (1.0 = new) per band, left to right.
+    pub wear: [f32; 3],
+
+    /// Cold pressure (kPa)
+    pub cold_pressure: f32,
+
+    /// Cold pressure requested for the next pit stop (kPa), if the car supports adjusting it.
+    pub requested_cold_pressure: Option<f32>,
+}
+
+impl Tire {
+    /// Temperature of the given band (degC)
+    pub fn temperature(&self, band: Band) -> f32 {
+        self.temperature[band.index()]
+    }
+
+    /// Remaining tread of the given band
+    pub fn wear(&self, band: Band) -> f32 {
+        self.wear[band.index()]
+    }
+
+    /// Mean temperature across the tire (degC)
+    pub fn mean_temperature(&self) -> f32 {
+        self.temperature.iter().sum::<f32>() / 3.0
+    }
+
+    /// Mean remaining tread across the tire
+    pub fn mean_wear(&self) -> f32 {
+        self.wear.iter().sum::<f32>() / 3.0
+    }
+}
+
+///
+/// State of all four tires.
+///
+/// # Examples
+///
+/// ```
+/// use iracing::tires::{Band, Corner, Tires};
+///
+/// let tires = Tires::default();
+/// let lf_middle = tires[Corner::LeftFront].temperature(Band::Middle);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tires {
+    pub tires: [Tire; 4],
+}
+
+impl Tires {
+    /// Get the tire fitted to a given corner
+    pub fn corner(&self, corner: Corner) -> &Tire {
+        &self.tires[corner.index()]
+    }
+
+    /// Get a mutable reference to the tire fitted to a given corner
+    pub fn corner_mut(&mut self, corner: Corner) -> &mut Tire {
+        &mut self.tires[corner.index()]
+    }
+}
+
+impl Index<Corner> for Tires {
+    type Output = Tire;
+
+    fn index(&self, corner: Corner) -> &Tire {
+        self.corner(corner)
+    }
+}
+
+/// Carcass temperature channels, by corner then band.
+pub const TEMPERATURE_CHANNELS: [[&str; 3]; 4] = [
+    ["LFtempCL", "LFtempCM", "LFtempCR"],
+    ["RFtempCL", "RFtempCM", "RFtempCR"],
+    ["LRtempCL", "LRtempCM", "LRtempCR"],
+    ["RRtempCL", "RRtempCM", "RRtempCR"],
+];
+
+/// Tread remaining channels, by corner then band.
+pub const WEAR_CHANNELS: [[&str; 3]; 4] = [
+    ["LFwearL", "LFwearM", "LFwearR"],
+    ["RFwearL", "RFwearM", "RFwearR"],
+    ["LRwearL", "LRwearM", "LRwearR"],
+    ["RRwearL", "RRwearM", "RRwearR"],
+];
+
+/// Cold pressure channels, by corner.
+pub const COLD_PRESSURE_CHANNELS: [&str; 4] = [
+    "LFcoldPressure",
+    "RFcoldPressure",
+    "LRcoldPressure",
+    "RRcoldPressure",
+];
+
+/// Pit stop cold pressure adjustment channels (Pa), by corner.
+pub const REQUESTED_PRESSURE_CHANNELS: [&str; 4] = [
+    "dpLFTireColdPress",
+    "dpRFTireColdPress",
+    "dpLRTireColdPress",
+    "dpRRTireColdPress",
+];
+
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+fn float(sample: &Sample, name: &'static str) -> Result<f32, String> {
+    sample
+        .get(name)?
+        .try_into()
+        .map_err(|e: &str| format!("{}: {}", name, e))
+}
+
+#[cfg(all(target_os = "windows", feature = "telemetry"))]
+impl TryFrom<&Sample> for Tires {
+    type Error = String;
+
+    /// Decode the tire channels from a telemetry sample.
+    ///
+    /// Fails if any of the temperature, wear or cold pressure channels is missing.
+    /// The requested pit stop pressures are optional.
+    fn try_from(sample: &Sample) -> Result<Self, Self::Error> {
+        let mut tires = Tires::default();
+
+        for (i, tire) in tires.tires.iter_mut().enumerate() {
+            for b in 0..3 {
+                tire.temperature[b] = float(sample, TEMPERATURE_CHANNELS[i][b])?;
+                tire.wear[b] = float(sample, WEAR_CHANNELS[i][b])?;
+            }
+
+            tire.cold_pressure = float(sample, COLD_PRESSURE_CHANNELS[i])?;
+            tire.requested_cold_pressure = float(sample, REQUESTED_PRESSURE_CHANNELS[i])
+                .ok()
+                .map(|pa| pa / 1000.0);
+        }
+
+        Ok(tires)
+    }
+}
+
+///
+/// Targets used when interpreting temperature spreads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpreadTargets {
+    /// Desired difference between inside and outside temperature (degC)
+    pub camber_spread: f32,
+
+    /// Allowed deviation from `camber_spread` before a change is suggested (degC)
+    pub camber_tolerance: f32,
+
+    /// Allowed difference between the middle and the mean of the edges (degC)
+    pub pressure_tolerance: f32,
+
+    /// Change in middle temperature expected per kPa of cold pressure (degC/kPa)
+    pub degrees_per_kpa: f32,
+}
+
+impl Default for SpreadTargets {
+    fn default() -> Self {
+        SpreadTargets {
+            camber_spread: 7.0,
+            camber_tolerance: 3.0,
+            pressure_tolerance: 3.0,
+            degrees_per_kpa: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CamberHint {
+    /// Inside is much hotter than outside
+    ReduceNegativeCamber,
+    /// Inside is not hot enough relative to the outside
+    IncreaseNegativeCamber,
+    Ok,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PressureHint {
+    /// Middle is hotter than the edges
+    Overinflated,
+    /// Middle is cooler than the edges
+    Underinflated,
+    Ok,
+}
+
+///
+/// Setup hints derived from the temperature spread of a tire.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpreadHint {
+    pub corner: Corner,
+    pub inside: f32,
+    pub middle: f32,
+    pub outside: f32,
+    pub camber: CamberHint,
+    pub pressure: PressureHint,
+}
+
+///
+/// Suggested cold pressure for a tire.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PressureSuggestion {
+    pub corner: Corner,
+    /// Current cold pressure (kPa)
+    pub current: f32,
+    /// Pressure currently requested for the next stop (kPa)
+    pub requested: Option<f32>,
+    /// Suggested cold pressure (kPa)
+    pub suggested: f32,
+    /// Change to make to the requested pressure to reach `suggested` (kPa).
+    ///
+    /// Relative to `requested` when the car reports it, otherwise to `current`, as a pit stop
+    /// without a request keeps the current pressure.
+    pub request_change: f32,
+}
+
+impl Tires {
+    /// Interpret the inside-middle-outside temperature spread of each tire.
+    pub fn spread_hints(&self, targets: &SpreadTargets) -> Vec<SpreadHint> {
+        Corner::ALL
+            .iter()
+            .map(|&corner| {
+                let tire = self.corner(corner);
+                let inside = tire.temperature(corner.inside_band());
+                let middle = tire.temperature(Band::Middle);
+                let outside = tire.temperature(corner.outside_band());
+
+                let spread = inside - outside;
+                let camber = if spread > targets.camber_spread + targets.camber_tolerance {
+                    CamberHint::ReduceNegativeCamber
+                } else if spread < targets.camber_spread - targets.camber_tolerance {
+                    CamberHint::IncreaseNegativeCamber
+                } else {
+                    CamberHint::Ok
+                };
+
+                let crown = middle - (inside + outside) / 2.0;
+                let pressure = if crown > targets.pressure_tolerance {
+                    PressureHint::Overinflated
+                } else if crown < -targets.pressure_tolerance {
+                    PressureHint::Underinflated
+                } else {
+                    PressureHint::Ok
+                };
+
+                SpreadHint {
+                    corner,
+                    inside,
+                    middle,
+                    outside,
+                    camber,
+                    pressure,
+                }
+            })
+            .collect()
+    }
+
+    /// Suggest target cold pressures which would even out the temperature across each tire.
+    ///
+    /// Each suggestion is made from the current cold pressure, and compared to the pressure
+    /// requested for the next pit stop (the `dp*TireColdPress` channels).
+    pub fn pressure_suggestions(&self, targets: &SpreadTargets) -> Vec<PressureSuggestion> {
+        Corner::ALL
+            .iter()
+            .map(|&corner| {
+                let tire = self.corner(corner);
+                let inside = tire.temperature(corner.inside_band());
+                let outside = tire.temperature(corner.outside_band());
+                let crown = tire.temperature(Band::Middle) - (inside + outside) / 2.0;
+
+                let adjustment = if crown.abs() > targets.pressure_tolerance {
+                    -crown / targets.degrees_per_kpa
+                } else {
+                    0.0
+                };
+
+                let suggested = tire.cold_pressure + adjustment;
+                let requested = tire.requested_cold_pressure.unwrap_or(tire.cold_pressure);
+
+                PressureSuggestion {
+                    corner,
+                    current: tire.cold_pressure,
+                    requested: tire.requested_cold_pressure,
+                    suggested,
+                    request_change: suggested - requested,
+                }
+            })
+            .collect()
+    }
+}
+
+///
+/// Tire wear over a stint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stint {
+    pub start_lap: i32,
+    pub end_lap: i32,
+    pub start: Tires,
+    pub end: Tires,
+}
+
+impl Stint {
+    /// Number of laps completed in the stint
+    pub fn laps(&self) -> i32 {
+        self.end_lap - self.start_lap
+    }
+
+    /// Mean tread lost per lap for a given corner.
+    ///
+    /// Returns `None` if no laps have been completed in the stint.
+    pub fn wear_per_lap(&self, corner: Corner) -> Option<f32> {
+        if self.laps() <= 0 {
+            return None;
+        }
+
+        let lost = self.start[corner].mean_wear() - self.end[corner].mean_wear();
+        Some(lost / self.laps() as f32)
+    }
+}
+
+///
+/// Tracks tire wear across multiple stints.
+///
+/// Feed it the tire state at the end of each lap (or whenever it changes), a new stint
+/// is started whenever any tire gains tread.
+///
+/// # Examples
+///
+/// ```
+/// use iracing::tires::{Corner, Tires, WearTracker};
+///
+/// let mut tracker = WearTracker::default();
+/// tracker.record(1, Tires::default());
+/// tracker.record(5, Tires::default());
+///
+/// let stint = &tracker.stints()[0];
+/// assert_eq!(stint.wear_per_lap(Corner::LeftFront), Some(0.0));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WearTracker {
+    stints: Vec<Stint>,
+}
+
+impl WearTracker {
+    /// Record the state of the tires on a given lap.
+    pub fn record(&mut self, lap: i32, tires: Tires) {
+        if let Some(current) = self.stints.last_mut() {
+            let fresh = Corner::ALL
+                .iter()
+                .any(|&c| tires[c].mean_wear() > current.end[c].mean_wear());
+
+            if !fresh {
+                current.end_lap = lap;
+                current.end = tires;
+                return;
+            }
+        }
+
+        self.stints.push(Stint {
+            start_lap: lap,
+            end_lap: lap,
+            start: tires,
+            end: tires,
+        });
+    }
+
+    /// All stints recorded so far
+    pub fn stints(&self) -> &[Stint] {
+        &self.stints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tire(temperature: [f32; 3], wear: f32) -> Tire {
+        Tire {
+            temperature,
+            wear: [wear; 3],
+            cold_pressure: 180.0,
+            requested_cold_pressure: None,
+        }
+    }
+
+    #[test]
+    fn spread_hints() {
+        let mut tires = Tires::default();
+        // Left front: inside (right band) far hotter than outside
+        *tires.corner_mut(Corner::LeftFront) = tire([70.0, 80.0, 95.0], 1.0);
+        // Right front: middle much hotter than the edges
+        *tires.corner_mut(Corner::RightFront) = tire([87.0, 95.0, 80.0], 1.0);
+        // Left rear is even
+        *tires.corner_mut(Corner::LeftRear) = tire([80.0, 84.0, 87.0], 1.0);
+        // Right rear: no inside-outside spread, and the middle cooler than the edges
+        *tires.corner_mut(Corner::RightRear) = tire([80.0, 75.0, 80.0], 1.0);
+        tires.corner_mut(Corner::RightRear).requested_cold_pressure = Some(190.0);
+
+        let hints = tires.spread_hints(&SpreadTargets::default());
+
+        assert_eq!(hints[0].camber, CamberHint::ReduceNegativeCamber);
+        assert_eq!(hints[1].camber, CamberHint::Ok);
+        assert_eq!(hints[1].pressure, PressureHint::Overinflated);
+        assert_eq!(hints[2].camber, CamberHint::Ok);
+        assert_eq!(hints[2].pressure, PressureHint::Ok);
+        assert_eq!(hints[3].camber, CamberHint::IncreaseNegativeCamber);
+        assert_eq!(hints[3].pressure, PressureHint::Underinflated);
+
+        let suggestions = tires.pressure_suggestions(&SpreadTargets::default());
+        assert!(suggestions[1].suggested < suggestions[1].current);
+        assert_eq!(suggestions[1].request_change, -23.0);
+        assert_eq!(suggestions[2].suggested, suggestions[2].current);
+        assert_eq!(suggestions[2].request_change, 0.0);
+
+        // The pit stop request already matches the suggestion
+        assert_eq!(suggestions[3].suggested, 190.0);
+        assert_eq!(suggestions[3].request_change, 0.0);
+    }
+
+    #[test]
+    fn wear_across_stints() {
+        let worn = |w: f32| {
+            let mut tires = Tires::default();
+            for c in Corner::ALL.iter() {
+                *tires.corner_mut(*c) = tire([80.0; 3], w);
+            }
+            tires
+        };
+
+        let mut tracker = WearTracker::default();
+        tracker.record(0, worn(1.0));
+        tracker.record(5, worn(0.9));
+        tracker.record(10, worn(0.8));
+        // Tire change
+        tracker.record(11, worn(1.0));
+        tracker.record(15, worn(0.96));
+
+        let stints = tracker.stints();
+        assert_eq!(stints.len(), 2);
+        assert_eq!(stints[0].laps(), 10);
+        assert!((stints[0].wear_per_lap(Corner::LeftFront).unwrap() - 0.02).abs() < 1e-6);
+        assert_eq!(stints[1].start_lap, 11);
+        assert!((stints[1].wear_per_lap(Corner::RightRear).unwrap() - 0.01).abs() < 1e-6);
+    }
+}
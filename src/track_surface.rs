@@ -1,5 +1,7 @@
 ///
 /// Track Surface Type
+///
+/// The material under a car (`irsdk_TrkSurf`), as given by `CarIdxTrackSurfaceMaterial`.
 
 #[derive(Debug, Clone, Copy)]
 pub enum TrackSurface {
@@ -25,15 +27,15 @@ impl From<i32> for TrackSurface {
         match idx {
             -1 => TrackSurface::NotInWorld,
             0 => TrackSurface::Undefined,
-            1 | 2 | 3 | 4 => TrackSurface::Asphalt(ix),
+            1..=4 => TrackSurface::Asphalt(ix),
             6 | 7 => TrackSurface::Concrete(ix - 4),
             8 | 9 => TrackSurface::RacingDirt(ix - 7),
             10 | 11 => TrackSurface::Paint(ix - 9),
-            12 | 13 | 14 | 15 => TrackSurface::Rumble(ix - 11),
-            16 | 17 | 18 | 19 => TrackSurface::Grass(ix - 15),
-            20 | 21 | 22 | 23 => TrackSurface::Dirt(ix - 19),
+            12..=15 => TrackSurface::Rumble(ix - 11),
+            16..=19 => TrackSurface::Grass(ix - 15),
+            20..=23 => TrackSurface::Dirt(ix - 19),
             24 => TrackSurface::Sand,
-            25 | 26 | 27 | 28 => TrackSurface::Gravel(ix - 24),
+            25..=28 => TrackSurface::Gravel(ix - 24),
             29 => TrackSurface::Grasscrete,
             30 => TrackSurface::Astroturf,
             _ => TrackSurface::Unknown(ix),